@@ -0,0 +1,5 @@
+// `sqlx::migrate!` embeds the migrations directory at compile time, so the
+// binary has to be rebuilt whenever a migration file is added or edited.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS subtasks;
+DROP TABLE IF EXISTS active_timers;
+DROP TABLE IF EXISTS time_entries;
+DROP TABLE IF EXISTS tasks;
+DROP TABLE IF EXISTS projects;
+DROP TABLE IF EXISTS users;
@@ -0,0 +1,27 @@
+DROP INDEX IF EXISTS idx_tasks_user_updated;
+DROP INDEX IF EXISTS idx_time_entries_task;
+DROP INDEX IF EXISTS idx_time_entries_user_start;
+
+ALTER TABLE subtasks
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+
+ALTER TABLE active_timers
+    ALTER COLUMN start_time TYPE TEXT USING to_char(start_time AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"'),
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+
+ALTER TABLE time_entries
+    ALTER COLUMN start_time TYPE TEXT USING to_char(start_time AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"'),
+    ALTER COLUMN end_time TYPE TEXT USING to_char(end_time AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"'),
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+
+ALTER TABLE tasks
+    ALTER COLUMN start_date TYPE TEXT USING to_char(start_date, 'YYYY-MM-DD'),
+    ALTER COLUMN due_date TYPE TEXT USING to_char(due_date, 'YYYY-MM-DD'),
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"'),
+    ALTER COLUMN updated_at TYPE TEXT USING to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+
+ALTER TABLE projects
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+
+ALTER TABLE users
+    ALTER COLUMN created_at TYPE TEXT USING to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
@@ -0,0 +1,32 @@
+-- Timestamps were stored as RFC3339 TEXT and compared as strings. Convert
+-- them to TIMESTAMPTZ, and the task start/due days to DATE. Empty strings
+-- written by older clients become NULL.
+
+ALTER TABLE users
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+ALTER TABLE projects
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+ALTER TABLE tasks
+    ALTER COLUMN start_date TYPE DATE USING NULLIF(start_date, '')::date,
+    ALTER COLUMN due_date TYPE DATE USING NULLIF(due_date, '')::date,
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz,
+    ALTER COLUMN updated_at TYPE TIMESTAMPTZ USING updated_at::timestamptz;
+
+ALTER TABLE time_entries
+    ALTER COLUMN start_time TYPE TIMESTAMPTZ USING start_time::timestamptz,
+    ALTER COLUMN end_time TYPE TIMESTAMPTZ USING NULLIF(end_time, '')::timestamptz,
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+ALTER TABLE active_timers
+    ALTER COLUMN start_time TYPE TIMESTAMPTZ USING start_time::timestamptz,
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+ALTER TABLE subtasks
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+-- Range lookups used by the dashboard, entry lists and reports
+CREATE INDEX IF NOT EXISTS idx_time_entries_user_start ON time_entries (user_id, start_time);
+CREATE INDEX IF NOT EXISTS idx_time_entries_task ON time_entries (task_id);
+CREATE INDEX IF NOT EXISTS idx_tasks_user_updated ON tasks (user_id, updated_at);
@@ -0,0 +1,2 @@
+-- IANA zone used to bucket a user's time into local days/weeks/months
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,6 @@
+ALTER TABLE time_entries DROP COLUMN IF EXISTS billable;
+ALTER TABLE users DROP COLUMN IF EXISTS hourly_rate_cents;
+ALTER TABLE projects
+    DROP COLUMN IF EXISTS hourly_rate_cents,
+    DROP COLUMN IF EXISTS billable;
+DROP TABLE IF EXISTS workspace_settings;
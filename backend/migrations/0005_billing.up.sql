@@ -0,0 +1,19 @@
+-- Workspace-wide settings (single row until workspaces exist)
+CREATE TABLE IF NOT EXISTS workspace_settings (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    currency TEXT NOT NULL DEFAULT 'USD',
+    default_hourly_rate_cents BIGINT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+INSERT INTO workspace_settings (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
+
+-- Rates are in minor units of the workspace currency. Resolution order for
+-- an entry: project rate, then the entry user's rate, then the default.
+ALTER TABLE projects
+    ADD COLUMN IF NOT EXISTS billable BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS hourly_rate_cents BIGINT;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS hourly_rate_cents BIGINT;
+
+-- New entries default to their project's billable flag
+ALTER TABLE time_entries ADD COLUMN IF NOT EXISTS billable BOOLEAN NOT NULL DEFAULT FALSE;
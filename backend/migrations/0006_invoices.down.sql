@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_time_entries_invoice;
+ALTER TABLE time_entries DROP COLUMN IF EXISTS invoice_id;
+DROP TABLE IF EXISTS invoice_lines;
+DROP TABLE IF EXISTS invoices;
+DROP SEQUENCE IF EXISTS invoice_number_seq;
@@ -0,0 +1,36 @@
+CREATE SEQUENCE IF NOT EXISTS invoice_number_seq;
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id TEXT PRIMARY KEY,
+    number TEXT NOT NULL UNIQUE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    project_id TEXT REFERENCES projects(id) ON DELETE SET NULL,
+    client_name TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'sent', 'paid')),
+    currency TEXT NOT NULL,
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL,
+    timezone TEXT NOT NULL DEFAULT 'UTC', -- zone the period and dates are shown in
+    total BIGINT NOT NULL DEFAULT 0,
+    notes TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_invoices_user ON invoices(user_id, created_at);
+
+-- Snapshot of what was billed; later rate or title changes don't alter it
+CREATE TABLE IF NOT EXISTS invoice_lines (
+    invoice_id TEXT NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    line_no INTEGER NOT NULL,
+    task_id TEXT REFERENCES tasks(id) ON DELETE SET NULL,
+    description TEXT NOT NULL,
+    minutes BIGINT NOT NULL,
+    hourly_rate_cents BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (invoice_id, line_no)
+);
+
+-- Set once an entry is on an invoice; deleting a draft releases its entries
+ALTER TABLE time_entries
+    ADD COLUMN IF NOT EXISTS invoice_id TEXT REFERENCES invoices(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_time_entries_invoice ON time_entries(invoice_id);
@@ -0,0 +1,3 @@
+ALTER TABLE active_timers
+    DROP COLUMN IF EXISTS paused_seconds,
+    DROP COLUMN IF EXISTS paused_at;
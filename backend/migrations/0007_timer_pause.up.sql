@@ -0,0 +1,5 @@
+-- Pause state of the running timer. paused_seconds sums the finished pauses;
+-- while paused_at is set the current pause is still open.
+ALTER TABLE active_timers
+    ADD COLUMN IF NOT EXISTS paused_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS paused_seconds BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+ALTER TABLE time_entries DROP COLUMN IF EXISTS raw_seconds;
+ALTER TABLE projects
+    DROP COLUMN IF EXISTS rounding_min_seconds,
+    DROP COLUMN IF EXISTS rounding_step_minutes,
+    DROP COLUMN IF EXISTS rounding_mode;
+ALTER TABLE workspace_settings
+    DROP COLUMN IF EXISTS rounding_min_seconds,
+    DROP COLUMN IF EXISTS rounding_step_minutes,
+    DROP COLUMN IF EXISTS rounding_mode;
@@ -0,0 +1,17 @@
+-- Rounding of timer-generated entries. Project columns override the
+-- workspace ones field by field; NULL means "inherit".
+--   mode: exact | nearest | up (to a multiple of step_minutes)
+--   min_seconds: shorter timer runs are discarded instead of saved
+ALTER TABLE workspace_settings
+    ADD COLUMN IF NOT EXISTS rounding_mode TEXT NOT NULL DEFAULT 'up'
+        CHECK (rounding_mode IN ('exact', 'nearest', 'up')),
+    ADD COLUMN IF NOT EXISTS rounding_step_minutes INTEGER NOT NULL DEFAULT 1 CHECK (rounding_step_minutes > 0),
+    ADD COLUMN IF NOT EXISTS rounding_min_seconds INTEGER NOT NULL DEFAULT 0 CHECK (rounding_min_seconds >= 0);
+
+ALTER TABLE projects
+    ADD COLUMN IF NOT EXISTS rounding_mode TEXT CHECK (rounding_mode IN ('exact', 'nearest', 'up')),
+    ADD COLUMN IF NOT EXISTS rounding_step_minutes INTEGER CHECK (rounding_step_minutes > 0),
+    ADD COLUMN IF NOT EXISTS rounding_min_seconds INTEGER CHECK (rounding_min_seconds >= 0);
+
+-- Measured timer duration before rounding; NULL for manual entries
+ALTER TABLE time_entries ADD COLUMN IF NOT EXISTS raw_seconds BIGINT;
@@ -0,0 +1,2 @@
+-- duration_minutes is kept up to date alongside, nothing to restore
+ALTER TABLE time_entries DROP COLUMN IF EXISTS duration_seconds;
@@ -0,0 +1,7 @@
+-- Seconds are the source of truth for durations; duration_minutes is kept
+-- (rounded from it) for existing clients.
+ALTER TABLE time_entries ADD COLUMN IF NOT EXISTS duration_seconds BIGINT;
+UPDATE time_entries SET duration_seconds = duration_minutes * 60 WHERE duration_seconds IS NULL;
+ALTER TABLE time_entries
+    ALTER COLUMN duration_seconds SET DEFAULT 0,
+    ALTER COLUMN duration_seconds SET NOT NULL;
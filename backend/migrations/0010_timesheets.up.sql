@@ -0,0 +1,31 @@
+-- One row per user and week; the period is fixed in the zone it was
+-- submitted from so the lock covers the same instants the user saw
+CREATE TABLE IF NOT EXISTS timesheets (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    week_start DATE NOT NULL, -- Monday
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL, -- exclusive
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    status TEXT NOT NULL DEFAULT 'submitted' CHECK (status IN ('submitted', 'approved', 'rejected', 'reopened')),
+    comment TEXT NOT NULL DEFAULT '', -- from the latest decision
+    submitted_at TIMESTAMPTZ NOT NULL,
+    decided_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    decided_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (user_id, week_start)
+);
+CREATE INDEX IF NOT EXISTS idx_timesheets_status ON timesheets(status, week_start);
+
+-- Append-only history of every state change
+CREATE TABLE IF NOT EXISTS timesheet_events (
+    id BIGSERIAL PRIMARY KEY,
+    timesheet_id TEXT NOT NULL REFERENCES timesheets(id) ON DELETE CASCADE,
+    actor_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+    action TEXT NOT NULL, -- submit | approve | reject | reopen
+    status TEXT NOT NULL, -- status after the change
+    comment TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_timesheet_events_timesheet ON timesheet_events(timesheet_id, id);
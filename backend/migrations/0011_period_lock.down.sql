@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS period_lock_log;
+ALTER TABLE workspace_settings
+    DROP COLUMN IF EXISTS locked_until,
+    DROP COLUMN IF EXISTS lock_timezone,
+    DROP COLUMN IF EXISTS lock_date;
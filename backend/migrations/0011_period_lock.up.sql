@@ -0,0 +1,17 @@
+-- Workspace-wide period close: nothing starting before locked_until (the
+-- end of lock_date in lock_timezone) may be created, changed or deleted
+ALTER TABLE workspace_settings
+    ADD COLUMN IF NOT EXISTS lock_date DATE,
+    ADD COLUMN IF NOT EXISTS lock_timezone TEXT NOT NULL DEFAULT 'UTC',
+    ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+
+-- Lock date changes and every superadmin override of the lock
+CREATE TABLE IF NOT EXISTS period_lock_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+    action TEXT NOT NULL, -- set | clear | create_entry | update_entry | delete_entry | stop_timer
+    lock_date DATE,       -- the new date for set/clear, the bypassed one for overrides
+    entry_id TEXT,        -- no FK: the entry may be gone
+    reason TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Where a session was last used from, shown in the session list
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS ip TEXT,
+    ADD COLUMN IF NOT EXISTS user_agent TEXT;
@@ -0,0 +1,7 @@
+ALTER TABLE workspace_settings DROP COLUMN IF EXISTS require_2fa_for_admins;
+DROP TABLE IF EXISTS login_challenges;
+DROP TABLE IF EXISTS recovery_codes;
+ALTER TABLE users
+    DROP COLUMN IF EXISTS totp_last_step,
+    DROP COLUMN IF EXISTS totp_enabled,
+    DROP COLUMN IF EXISTS totp_secret;
@@ -0,0 +1,23 @@
+-- Failed sign-in attempts per account (by username, whether or not it
+-- exists) and per client IP, for backoff and temporary lockouts
+CREATE TABLE IF NOT EXISTS login_throttle (
+    scope TEXT NOT NULL CHECK (scope IN ('account', 'ip')),
+    key TEXT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ NOT NULL,
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (scope, key)
+);
+
+-- Audit trail of failed attempts, lockouts, unlocks and registrations
+CREATE TABLE IF NOT EXISTS login_events (
+    id BIGSERIAL PRIMARY KEY,
+    event TEXT NOT NULL CHECK (event IN ('login_failed', 'two_factor_failed', 'account_locked', 'ip_locked', 'unlocked', 'registered')),
+    username TEXT,
+    user_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+    ip TEXT,
+    actor_id TEXT REFERENCES users(id) ON DELETE SET NULL, -- admin who unlocked
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_login_events_user ON login_events(user_id);
+CREATE INDEX IF NOT EXISTS idx_login_events_ip ON login_events(ip, event, created_at);
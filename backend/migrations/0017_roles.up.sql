@@ -0,0 +1,45 @@
+-- Roles are named permission sets. `level` ranks them: a user may only
+-- manage users of lower-level roles (or the same level, with
+-- users.manage_peers). Built-in roles can be edited but not deleted.
+CREATE TABLE IF NOT EXISTS roles (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    level INTEGER NOT NULL,
+    builtin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role TEXT NOT NULL REFERENCES roles(name) ON UPDATE CASCADE ON DELETE CASCADE,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+INSERT INTO roles (name, description, level, builtin) VALUES
+    ('superadmin', 'Full access', 100, TRUE),
+    ('admin', 'Manages users, reviews timesheets, closes periods', 50, TRUE),
+    ('user', 'Tracks own time', 10, TRUE)
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role, permission)
+SELECT r.role, p.permission
+FROM (VALUES
+    ('user', ARRAY['account.self', 'time.track', 'invoices.manage']),
+    ('admin', ARRAY['account.self', 'time.track', 'invoices.manage',
+                    'users.view', 'users.manage', 'users.assign_roles',
+                    'reports.view', 'timesheets.review', 'security.audit', 'period.lock']),
+    ('superadmin', ARRAY['account.self', 'time.track', 'invoices.manage',
+                         'users.view', 'users.manage', 'users.assign_roles', 'users.manage_peers',
+                         'reports.view', 'timesheets.review', 'security.audit', 'period.lock',
+                         'period.reopen', 'settings.manage', 'roles.manage'])
+) AS r(role, permissions)
+CROSS JOIN LATERAL UNNEST(r.permissions) AS p(permission)
+ON CONFLICT DO NOTHING;
+
+-- Any other role name in use becomes a role without permissions
+INSERT INTO roles (name, level)
+SELECT DISTINCT role, 0 FROM users
+ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_fkey;
+ALTER TABLE users ADD CONSTRAINT users_role_fkey FOREIGN KEY (role) REFERENCES roles(name) ON UPDATE CASCADE;
@@ -0,0 +1,48 @@
+-- Folds every workspace back into one. Where a user has several roles or
+-- timesheets for the same week, the oldest workspace's win.
+ALTER TABLE sessions DROP COLUMN IF EXISTS workspace_id;
+
+DROP INDEX IF EXISTS idx_audit_events_workspace;
+ALTER TABLE audit_events DROP COLUMN IF EXISTS workspace_id;
+
+ALTER TABLE period_lock_log DROP COLUMN IF EXISTS workspace_id;
+
+ALTER TABLE timesheets DROP CONSTRAINT IF EXISTS timesheets_workspace_id_user_id_week_start_key;
+DELETE FROM timesheets t
+USING timesheets o, workspaces tw, workspaces ow
+WHERE o.user_id = t.user_id AND o.week_start = t.week_start AND o.id <> t.id
+  AND tw.id = t.workspace_id AND ow.id = o.workspace_id
+  AND (ow.created_at, ow.id) < (tw.created_at, tw.id);
+ALTER TABLE timesheets DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE timesheets DROP CONSTRAINT IF EXISTS timesheets_user_id_week_start_key;
+ALTER TABLE timesheets ADD CONSTRAINT timesheets_user_id_week_start_key UNIQUE (user_id, week_start);
+
+ALTER TABLE invoices DROP COLUMN IF EXISTS workspace_id;
+DROP INDEX IF EXISTS idx_tasks_workspace;
+ALTER TABLE tasks DROP COLUMN IF EXISTS workspace_id;
+DROP INDEX IF EXISTS idx_projects_workspace;
+ALTER TABLE projects DROP COLUMN IF EXISTS workspace_id;
+
+DELETE FROM workspace_settings WHERE workspace_id <> (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1);
+ALTER TABLE workspace_settings DROP CONSTRAINT IF EXISTS workspace_settings_pkey;
+ALTER TABLE workspace_settings DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE workspace_settings ADD COLUMN IF NOT EXISTS id BOOLEAN NOT NULL DEFAULT TRUE CHECK (id);
+ALTER TABLE workspace_settings ADD PRIMARY KEY (id);
+INSERT INTO workspace_settings (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
+
+ALTER TABLE roles DROP COLUMN IF EXISTS workspace_id;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'user',
+                  ADD COLUMN IF NOT EXISTS hourly_rate_cents BIGINT;
+UPDATE users u SET role = m.role, hourly_rate_cents = m.hourly_rate_cents
+FROM (
+    SELECT DISTINCT ON (m.user_id) m.user_id, m.role, m.hourly_rate_cents
+    FROM workspace_members m JOIN workspaces w ON w.id = m.workspace_id
+    ORDER BY m.user_id, w.created_at, w.id
+) m
+WHERE m.user_id = u.id;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_fkey;
+ALTER TABLE users ADD CONSTRAINT users_role_fkey FOREIGN KEY (role) REFERENCES roles(name) ON UPDATE CASCADE;
+
+DROP TABLE IF EXISTS workspace_members;
+DROP TABLE IF EXISTS workspaces;
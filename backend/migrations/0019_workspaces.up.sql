@@ -0,0 +1,87 @@
+-- Workspaces let several teams share one deployment. Users join them with
+-- a role (and rate) per workspace; projects, tasks and everything reported
+-- on them belong to exactly one. What existed so far becomes the first
+-- workspace.
+CREATE TABLE IF NOT EXISTS workspaces (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+INSERT INTO workspaces (id, name)
+SELECT gen_random_uuid()::TEXT, 'Default'
+WHERE NOT EXISTS (SELECT 1 FROM workspaces);
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    workspace_id TEXT NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL REFERENCES roles(name) ON UPDATE CASCADE,
+    hourly_rate_cents BIGINT, -- NULL falls back to the workspace default
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_workspace_members_user ON workspace_members(user_id);
+
+INSERT INTO workspace_members (workspace_id, user_id, role, hourly_rate_cents, created_at)
+SELECT (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1), u.id, u.role, u.hourly_rate_cents, u.created_at
+FROM users u
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_fkey;
+ALTER TABLE users DROP COLUMN IF EXISTS role, DROP COLUMN IF EXISTS hourly_rate_cents;
+
+-- Built-in roles (no workspace) are shared by every workspace; custom ones
+-- belong to the workspace that created them
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE roles SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1)
+WHERE NOT builtin AND workspace_id IS NULL;
+
+-- One settings row per workspace
+ALTER TABLE workspace_settings ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE workspace_settings SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1)
+WHERE workspace_id IS NULL;
+ALTER TABLE workspace_settings DROP CONSTRAINT IF EXISTS workspace_settings_pkey;
+ALTER TABLE workspace_settings DROP COLUMN IF EXISTS id;
+ALTER TABLE workspace_settings ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE workspace_settings ADD PRIMARY KEY (workspace_id);
+INSERT INTO workspace_settings (workspace_id) SELECT id FROM workspaces ON CONFLICT DO NOTHING;
+
+-- Workspace-owned records
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE projects SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
+ALTER TABLE projects ALTER COLUMN workspace_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_projects_workspace ON projects(workspace_id);
+
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE tasks SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
+ALTER TABLE tasks ALTER COLUMN workspace_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tasks_workspace ON tasks(workspace_id);
+
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE invoices SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
+ALTER TABLE invoices ALTER COLUMN workspace_id SET NOT NULL;
+
+-- A week is submitted once per workspace, covering that workspace's entries
+ALTER TABLE timesheets ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE timesheets SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
+ALTER TABLE timesheets ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE timesheets DROP CONSTRAINT IF EXISTS timesheets_user_id_week_start_key;
+ALTER TABLE timesheets DROP CONSTRAINT IF EXISTS timesheets_workspace_id_user_id_week_start_key;
+ALTER TABLE timesheets ADD CONSTRAINT timesheets_workspace_id_user_id_week_start_key UNIQUE (workspace_id, user_id, week_start);
+
+ALTER TABLE period_lock_log ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE CASCADE;
+UPDATE period_lock_log SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
+ALTER TABLE period_lock_log ALTER COLUMN workspace_id SET NOT NULL;
+
+-- Audit events keep no foreign keys; NULL for account-level events such as
+-- sign-ups and logins. Filling in the existing rows is the one change the
+-- append-only trigger has to let through.
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS workspace_id TEXT;
+ALTER TABLE audit_events DISABLE TRIGGER audit_events_no_change;
+UPDATE audit_events SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1)
+WHERE workspace_id IS NULL AND action NOT IN ('user.register', 'auth.login', 'auth.logout');
+ALTER TABLE audit_events ENABLE TRIGGER audit_events_no_change;
+CREATE INDEX IF NOT EXISTS idx_audit_events_workspace ON audit_events(workspace_id, id);
+
+-- The workspace a session is working in; switching updates it
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS workspace_id TEXT REFERENCES workspaces(id) ON DELETE SET NULL;
+UPDATE sessions SET workspace_id = (SELECT id FROM workspaces ORDER BY created_at, id LIMIT 1) WHERE workspace_id IS NULL;
@@ -0,0 +1,17 @@
+-- Projects are shared through membership. Owners manage the project and
+-- its members, editors add tasks and track time on them, viewers only see
+-- them. `projects.user_id` stays as the creator; each creator becomes the
+-- first owner (legacy projects without one start with no members).
+CREATE TABLE IF NOT EXISTS project_members (
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (project_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_project_members_user ON project_members(user_id);
+
+INSERT INTO project_members (project_id, user_id, role, created_at)
+SELECT id, user_id, 'owner', created_at FROM projects
+WHERE user_id IS NOT NULL
+ON CONFLICT DO NOTHING;
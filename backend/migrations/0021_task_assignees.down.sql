@@ -0,0 +1,3 @@
+DELETE FROM role_permissions WHERE permission = 'tasks.assign';
+DROP INDEX IF EXISTS idx_tasks_assignee;
+ALTER TABLE tasks DROP COLUMN IF EXISTS assignee_id;
@@ -0,0 +1,9 @@
+-- Tasks can be assigned to someone other than their creator (`user_id`).
+-- Assignees see the task and can track time on it.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS assignee_id TEXT REFERENCES users(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_tasks_assignee ON tasks(assignee_id);
+
+-- Assigning tasks one didn't create
+INSERT INTO role_permissions (role, permission)
+SELECT name, 'tasks.assign' FROM roles WHERE builtin AND name IN ('superadmin', 'admin')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,9 @@
+-- Only the oldest timer of a shared task survives
+DELETE FROM active_timers a
+USING active_timers b
+WHERE a.task_id = b.task_id AND (a.created_at, a.id) > (b.created_at, b.id);
+
+DROP INDEX IF EXISTS idx_active_timers_task;
+ALTER TABLE active_timers DROP CONSTRAINT IF EXISTS active_timers_user_id_key;
+ALTER TABLE active_timers DROP CONSTRAINT IF EXISTS active_timers_task_id_key;
+ALTER TABLE active_timers ADD CONSTRAINT active_timers_task_id_key UNIQUE (task_id);
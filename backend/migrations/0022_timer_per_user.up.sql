@@ -0,0 +1,10 @@
+-- Tasks are shared by project members and assignees, so several people can
+-- run a timer on the same task. What's limited is one timer per user.
+DELETE FROM active_timers a
+USING active_timers b
+WHERE a.user_id = b.user_id AND (a.created_at, a.id) < (b.created_at, b.id);
+
+ALTER TABLE active_timers DROP CONSTRAINT IF EXISTS active_timers_task_id_key;
+ALTER TABLE active_timers DROP CONSTRAINT IF EXISTS active_timers_user_id_key;
+ALTER TABLE active_timers ADD CONSTRAINT active_timers_user_id_key UNIQUE (user_id);
+CREATE INDEX IF NOT EXISTS idx_active_timers_task ON active_timers(task_id);
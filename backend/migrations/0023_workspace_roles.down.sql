@@ -0,0 +1,27 @@
+-- Names become global again: custom roles sharing a name with an older
+-- role get the start of their id appended, for their members too
+UPDATE workspace_members m SET role = m.role || '-' || LEFT(r.id, 8)
+FROM roles r
+WHERE r.workspace_id = m.workspace_id AND r.name = m.role
+  AND EXISTS (SELECT 1 FROM roles o WHERE o.name = r.name AND o.id <> r.id
+                AND (o.workspace_id IS NULL OR (o.created_at, o.id) < (r.created_at, r.id)));
+UPDATE roles r SET name = r.name || '-' || LEFT(r.id, 8)
+WHERE r.workspace_id IS NOT NULL
+  AND EXISTS (SELECT 1 FROM roles o WHERE o.name = r.name AND o.id <> r.id
+                AND (o.workspace_id IS NULL OR (o.created_at, o.id) < (r.created_at, r.id)));
+
+ALTER TABLE role_permissions ADD COLUMN IF NOT EXISTS role TEXT;
+UPDATE role_permissions rp SET role = r.name FROM roles r WHERE r.id = rp.role_id;
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_role_id_fkey;
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_pkey;
+ALTER TABLE role_permissions DROP COLUMN IF EXISTS role_id;
+ALTER TABLE role_permissions ALTER COLUMN role SET NOT NULL;
+
+DROP INDEX IF EXISTS idx_roles_workspace_name;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_pkey;
+ALTER TABLE roles ADD PRIMARY KEY (name);
+ALTER TABLE roles DROP COLUMN IF EXISTS id;
+
+ALTER TABLE role_permissions ADD PRIMARY KEY (role, permission);
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_role_fkey FOREIGN KEY (role) REFERENCES roles(name) ON UPDATE CASCADE ON DELETE CASCADE;
+ALTER TABLE workspace_members ADD CONSTRAINT workspace_members_role_fkey FOREIGN KEY (role) REFERENCES roles(name) ON UPDATE CASCADE;
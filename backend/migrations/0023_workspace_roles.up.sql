@@ -0,0 +1,24 @@
+-- Custom role names only have to be unique within their workspace; the
+-- built-in roles (no workspace) keep names no workspace can reuse. Roles
+-- get an id, which their permissions hang off. Members keep naming their
+-- role, resolved among the built-in roles and their workspace's own.
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS id TEXT;
+UPDATE roles SET id = gen_random_uuid()::TEXT WHERE id IS NULL;
+ALTER TABLE roles ALTER COLUMN id SET DEFAULT gen_random_uuid()::TEXT;
+ALTER TABLE roles ALTER COLUMN id SET NOT NULL;
+
+ALTER TABLE role_permissions ADD COLUMN IF NOT EXISTS role_id TEXT;
+UPDATE role_permissions rp SET role_id = r.id FROM roles r WHERE r.name = rp.role AND rp.role_id IS NULL;
+
+ALTER TABLE workspace_members DROP CONSTRAINT IF EXISTS workspace_members_role_fkey;
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_role_fkey;
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_pkey;
+ALTER TABLE role_permissions DROP COLUMN IF EXISTS role;
+
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_pkey;
+ALTER TABLE roles ADD PRIMARY KEY (id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_workspace_name ON roles (COALESCE(workspace_id, ''), name);
+
+ALTER TABLE role_permissions ALTER COLUMN role_id SET NOT NULL;
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_role_id_fkey FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE;
+ALTER TABLE role_permissions ADD PRIMARY KEY (role_id, permission);
@@ -0,0 +1,12 @@
+-- Admins invite existing users into their workspace; the invitee decides
+-- whether to join. One pending invite per user and workspace.
+CREATE TABLE IF NOT EXISTS workspace_invites (
+    id TEXT PRIMARY KEY,
+    workspace_id TEXT NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    invited_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (workspace_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_workspace_invites_user ON workspace_invites(user_id);
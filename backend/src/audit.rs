@@ -0,0 +1,89 @@
+use actix_web::{HttpMessage, HttpRequest};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{query, query_scalar};
+
+use crate::db::DbPool;
+use crate::models::user::AuthClaims;
+use crate::session;
+
+/// Who an event is attributed to, from where, and in which workspace.
+pub struct Actor {
+    pub id: Option<String>,
+    pub ip: Option<String>,
+    pub workspace_id: Option<String>,
+}
+
+impl Actor {
+    // The signed-in caller, in their active workspace
+    pub fn of(req: &HttpRequest) -> Self {
+        let claims = req.extensions().get::<AuthClaims>().map(|c| (c.sub.clone(), c.wid.clone()));
+        let (id, workspace_id) = claims.unzip();
+        Actor { id, ip: session::client_ip(req), workspace_id }
+    }
+
+    // A user acting before they have a session, i.e. signing up or in;
+    // these events belong to no workspace
+    pub fn user(req: &HttpRequest, user_id: &str) -> Self {
+        Actor { id: Some(user_id.to_string()), ip: session::client_ip(req), workspace_id: None }
+    }
+}
+
+pub fn json(value: &impl Serialize) -> Option<Value> {
+    serde_json::to_value(value).ok()
+}
+
+// A users row as stored, without secrets
+pub async fn user(pool: &DbPool, user_id: &str) -> Option<Value> {
+    query_scalar("SELECT to_jsonb(u) - 'password_hash' - 'totp_secret' - 'totp_last_step' FROM users u WHERE u.id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+}
+
+// A task with the subtasks and time entries that go with it when it is
+// deleted
+pub async fn task(pool: &DbPool, task_id: &str) -> Option<Value> {
+    query_scalar(
+        "SELECT to_jsonb(t) || jsonb_build_object(
+                    'subtasks', (SELECT COALESCE(jsonb_agg(to_jsonb(s) ORDER BY s.position), '[]') FROM subtasks s WHERE s.task_id = t.id),
+                    'entries', (SELECT COALESCE(jsonb_agg(to_jsonb(e) ORDER BY e.start_time), '[]') FROM time_entries e WHERE e.task_id = t.id))
+         FROM tasks t WHERE t.id = $1"
+    )
+    .bind(task_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None)
+}
+
+// Appends to the audit trail. Like the other logs, a failure here doesn't
+// undo the change it describes.
+pub async fn log(
+    pool: &DbPool,
+    actor: &Actor,
+    action: &str,
+    target_type: &str,
+    target_id: &str,
+    before: Option<Value>,
+    after: Option<Value>,
+) {
+    let result = query(
+        "INSERT INTO audit_events (actor_id, action, target_type, target_id, before, after, ip, workspace_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+    )
+    .bind(&actor.id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(before)
+    .bind(after)
+    .bind(&actor.ip)
+    .bind(&actor.workspace_id)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("audit log error: {:?}", e);
+    }
+}
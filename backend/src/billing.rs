@@ -0,0 +1,48 @@
+use crate::db::DbPool;
+
+// Hourly rate (minor units of the workspace currency) that applies to an
+// entry: the project's rate, then the rate of the user who logged the time,
+// then the workspace default, all in the workspace of the entry's task.
+// Expects the entry aliased `e` and its project `p` (LEFT JOINed) in the
+// surrounding query.
+pub const ENTRY_RATE_SQL: &str = "COALESCE(
+    p.hourly_rate_cents,
+    (SELECT rm.hourly_rate_cents FROM tasks rt JOIN workspace_members rm ON rm.workspace_id = rt.workspace_id
+     WHERE rt.id = e.task_id AND rm.user_id = e.user_id),
+    (SELECT rs.default_hourly_rate_cents FROM tasks rt JOIN workspace_settings rs ON rs.workspace_id = rt.workspace_id
+     WHERE rt.id = e.task_id),
+    0)";
+
+// Billable flag for a new entry on task `$task_param` when the caller did
+// not pass one: the project's flag, non-billable for tasks without project.
+pub fn default_billable_sql(task_param: &str) -> String {
+    format!(
+        "COALESCE((SELECT p.billable FROM tasks t JOIN projects p ON p.id = t.project_id WHERE t.id = {}), FALSE)",
+        task_param
+    )
+}
+
+// SUM of billable minutes over the entries matching `cond`
+pub fn billable_minutes_sql(cond: &str) -> String {
+    format!(
+        "ROUND(COALESCE(SUM(CASE WHEN e.billable AND {} THEN e.duration_seconds ELSE 0 END), 0) / 60.0)::BIGINT",
+        cond
+    )
+}
+
+// SUM of amounts over the entries matching `cond`, rounded to whole cents
+pub fn amount_sql(cond: &str) -> String {
+    format!(
+        "COALESCE(ROUND(SUM(CASE WHEN e.billable AND {} THEN e.duration_seconds * {} ELSE 0 END) / 3600.0), 0)::BIGINT",
+        cond, ENTRY_RATE_SQL
+    )
+}
+
+pub async fn currency(pool: &DbPool, workspace_id: &str) -> String {
+    sqlx::query_scalar("SELECT currency FROM workspace_settings WHERE workspace_id = $1")
+        .bind(workspace_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_else(|| "USD".into())
+}
@@ -0,0 +1,62 @@
+use std::io::{Error, ErrorKind};
+
+use sqlx::query_as;
+
+use super::{DbPool, MIGRATOR};
+
+// `sic_mundus_backend migrate <command>` — runs migrations without starting
+// the HTTP server, e.g. from a deploy script before swapping containers.
+//
+//   migrate up                 apply every pending migration
+//   migrate down [VERSION]     revert down to VERSION (default: the latest one)
+//   migrate status             list migrations and whether they are applied
+pub async fn run_cli(pool: &DbPool, args: &[String]) -> std::io::Result<()> {
+    match args.first().map(String::as_str).unwrap_or("up") {
+        "up" => {
+            MIGRATOR.run(pool).await.map_err(to_io)?;
+            println!("✅ Database is up to date");
+        }
+        "down" => {
+            let applied = applied_versions(pool).await;
+            let target = match args.get(1) {
+                Some(v) => v
+                    .parse::<i64>()
+                    .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Invalid version: {}", v)))?,
+                // Revert only the most recent migration
+                None => applied.iter().rev().nth(1).map(|(v, _)| *v).unwrap_or(0),
+            };
+            MIGRATOR.undo(pool, target).await.map_err(to_io)?;
+            println!("✅ Reverted to version {}", target);
+        }
+        "status" => {
+            let applied = applied_versions(pool).await;
+            for m in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+                let state = match applied.iter().find(|(v, _)| *v == m.version) {
+                    Some((_, checksum)) if checksum.as_slice() != m.checksum.as_ref() => "applied (checksum mismatch!)",
+                    Some(_) => "applied",
+                    None => "pending",
+                };
+                println!("{:04}  {:<40} {}", m.version, m.description, state);
+            }
+        }
+        other => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown migrate command '{}' (expected up, down or status)", other),
+            ))
+        }
+    }
+    Ok(())
+}
+
+async fn applied_versions(pool: &DbPool) -> Vec<(i64, Vec<u8>)> {
+    // The table does not exist until the first migration run
+    query_as("SELECT version, checksum FROM _sqlx_migrations WHERE success ORDER BY version")
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+}
+
+fn to_io(e: sqlx::migrate::MigrateError) -> Error {
+    Error::other(e)
+}
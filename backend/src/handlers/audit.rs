@@ -0,0 +1,68 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::db::DbPool;
+use crate::models::audit::*;
+use crate::pagination::{self, csv, Cursor};
+use crate::rbac;
+
+const AUDIT_ORDER: &str = "id:desc";
+
+// ─── GET /api/admin/audit ───
+
+// Newest first. Events of the active workspace, plus account-level ones
+// (sign-ups, logins) of users the caller manages there.
+pub async fn get_audit_events(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<AuditQuery>) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let limit = match pagination::parse_limit(query.limit) {
+        Ok(l) => l.unwrap_or(100),
+        Err(resp) => return resp,
+    };
+    let before_id = match query.cursor.as_deref().filter(|t| !t.is_empty()) {
+        Some(token) => match Cursor::decode(token).filter(|c| c.order == AUDIT_ORDER).and_then(|c| c.id.parse::<i64>().ok()) {
+            Some(id) => Some(id),
+            None => return HttpResponse::BadRequest().body("Invalid cursor"),
+        },
+        None => None,
+    };
+    let users = match rbac::reachable_users(pool.get_ref(), &grants).await {
+        Ok(users) => users,
+        Err(e) => {
+            eprintln!("get_audit_events error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(
+        "SELECT e.id, e.actor_id, a.username AS actor_username, e.action, e.target_type, e.target_id,
+                e.before, e.after, e.ip, e.created_at
+         FROM audit_events e
+         LEFT JOIN users a ON a.id = e.actor_id
+         WHERE (e.workspace_id = "
+    );
+    qb.push_bind(grants.workspace_id.clone());
+    qb.push(" OR (e.workspace_id IS NULL AND (e.actor_id = ANY(").push_bind(users.clone())
+      .push(") OR e.target_id = ANY(").push_bind(users).push("))))");
+    if let Some(v) = csv(&query.actor_id)    { qb.push(" AND e.actor_id = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.action)      { qb.push(" AND e.action = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.target_type) { qb.push(" AND e.target_type = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.target_id)   { qb.push(" AND e.target_id = ANY(").push_bind(v).push(")"); }
+    if let Some(from) = query.from { qb.push(" AND e.created_at >= ").push_bind(from); }
+    if let Some(to) = query.to     { qb.push(" AND e.created_at < ").push_bind(to); }
+    if let Some(id) = before_id    { qb.push(" AND e.id < ").push_bind(id); }
+    qb.push(" ORDER BY e.id DESC LIMIT ").push_bind(limit + 1);
+
+    match qb.build_query_as::<AuditEvent>().fetch_all(pool.get_ref()).await {
+        Ok(rows) => pagination::page_response(rows, Some(limit), |e| Cursor {
+            order: AUDIT_ORDER.to_string(),
+            value: e.created_at.to_rfc3339(),
+            id: e.id.to_string(),
+        }),
+        Err(e) => {
+            eprintln!("get_audit_events error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use crate::{
+    audit::{self, Actor},
     db::DbPool,
-    models::user::{AuthClaims, AuthResponse, LoginRequest, RegisterRequest, User},
+    handlers::{user::load_member, workspace::create_workspace},
+    models::two_factor::{LoginChallenge, VerifyLoginRequest},
+    models::user::{AuthClaims, AuthResponse, LoginRequest, LogoutRequest, RefreshRequest, RegisterRequest, User},
+    session, throttle,
+    two_factor::{self, Redeemed},
 };
 use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use argon2::{
@@ -8,14 +13,19 @@ use argon2::{
     Argon2,
 };
 use chrono::Utc;
-use jsonwebtoken::{encode, EncodingKey, Header};
 use sqlx::{query, query_as};
 use uuid::Uuid;
 
 pub async fn register(
     pool: web::Data<DbPool>,
+    req: HttpRequest,
     body: web::Json<RegisterRequest>,
 ) -> impl Responder {
+    let ip = session::client_ip(&req);
+    if let Err(resp) = throttle::check_register(pool.get_ref(), ip.as_deref()).await {
+        return resp;
+    }
+
     // 1. Check if user exists
     let user_exists = query("SELECT id FROM users WHERE username = $1")
         .bind(&body.username)
@@ -34,40 +44,69 @@ pub async fn register(
         .expect("Failed to hash password")
         .to_string();
 
-    // 3. Determine role (first user = admin)
-    let user_count: i64 = query_as::<_, (i64,)>("SELECT COUNT(*) FROM users")
-        .fetch_one(pool.get_ref())
-        .await
-        .map(|r| r.0)
-        .unwrap_or(0);
-
-    let role = if user_count == 0 { "superadmin" } else { "user" };
     let full_name = body.full_name.trim().to_string();
     if full_name.is_empty() {
         return HttpResponse::BadRequest().body("Full name is required");
     }
     let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-
-    // 4. Insert user
-    let insert_result = query(
-        "INSERT INTO users (id, username, full_name, password_hash, role, created_at) VALUES ($1, $2, $3, $4, $5, $6)",
-    )
-    .bind(&id)
-    .bind(&body.username)
-    .bind(&full_name)
-    .bind(&password_hash)
-    .bind(role)
-    .bind(&created_at)
-    .execute(pool.get_ref())
+    let created_at = Utc::now();
+
+    // 3. Insert user. Registrants join the workspace the install starts
+    // with as plain users, except the very first, who becomes its
+    // superadmin. More workspaces come from POST /api/workspaces.
+    let result: Result<(String, &str), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        query("INSERT INTO users (id, username, full_name, password_hash, created_at) VALUES ($1, $2, $3, $4, $5)")
+            .bind(&id)
+            .bind(&body.username)
+            .bind(&full_name)
+            .bind(&password_hash)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+
+        let default: Option<(String, bool)> = query_as(
+            "SELECT w.id, NOT EXISTS (SELECT 1 FROM workspace_members) AS first
+             FROM workspaces w ORDER BY w.created_at, w.id LIMIT 1"
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let joined = match default {
+            Some((workspace_id, first)) => {
+                let role = if first { "superadmin" } else { "user" };
+                query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES ($1, $2, $3)")
+                    .bind(&workspace_id)
+                    .bind(&id)
+                    .bind(role)
+                    .execute(&mut *tx)
+                    .await?;
+                (workspace_id, role)
+            }
+            // Every workspace was deleted: start one
+            None => (create_workspace(&mut tx, &format!("{}'s workspace", full_name), &id).await?, "superadmin"),
+        };
+
+        tx.commit().await?;
+        Ok(joined)
+    }
     .await;
 
-    match insert_result {
-        Ok(_) => {
-            // 5. Generate Token
-            let token = generate_token(&id, role);
+    let (workspace_id, role) = match result {
+        Ok(joined) => joined,
+        Err(e) => {
+            eprintln!("register error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    throttle::log(pool.get_ref(), "registered", Some(&body.username), Some(&id), ip.as_deref(), None).await;
+    let after = audit::user(pool.get_ref(), &id).await;
+    audit::log(pool.get_ref(), &Actor::user(&req, &id), "user.register", "user", &id, None, after).await;
+
+    // 4. Start a session
+    match session::create(pool.get_ref(), &req, &id, &workspace_id, role).await {
+        Ok(tokens) => {
             HttpResponse::Ok().json(AuthResponse {
-                token,
+                tokens,
                 user: User {
                     id,
                     username: body.username.clone(),
@@ -76,7 +115,12 @@ pub async fn register(
                     password_hash: "".to_string(),
                     created_at,
                     force_change_password: false,
+                    timezone: "UTC".to_string(),
+                    hourly_rate_cents: None,
+                    totp_enabled: false,
                 },
+                two_factor_setup_required: two_factor::required_for(pool.get_ref(), &workspace_id, role).await,
+                workspace_id,
             })
         }
         Err(_) => HttpResponse::InternalServerError().finish(),
@@ -85,8 +129,15 @@ pub async fn register(
 
 pub async fn login(
     pool: web::Data<DbPool>,
+    req: HttpRequest,
     body: web::Json<LoginRequest>,
 ) -> impl Responder {
+    // 0. Locked out or backing off?
+    let ip = session::client_ip(&req);
+    if let Err(resp) = throttle::check_login(pool.get_ref(), &body.username, ip.as_deref()).await {
+        return resp;
+    }
+
     // 1. Find user
     let user = query_as::<_, User>("SELECT * FROM users WHERE username = $1")
         .bind(&body.username)
@@ -101,55 +152,173 @@ pub async fn login(
             .verify_password(body.password.as_bytes(), &parsed_hash)
             .is_ok()
         {
-            // 3. Generate token
-            let token = generate_token(&user.id, &user.role);
-            return HttpResponse::Ok().json(AuthResponse {
-                token,
-                user,
-            });
+            // 3. With 2FA, the session waits for a code
+            if user.totp_enabled {
+                return match two_factor::create_challenge(pool.get_ref(), &user.id).await {
+                    Ok(challenge_token) => HttpResponse::Ok().json(LoginChallenge {
+                        two_factor_required: true,
+                        challenge_token,
+                        expires_in: two_factor::CHALLENGE_MINUTES * 60,
+                    }),
+                    Err(e) => {
+                        eprintln!("login challenge error: {:?}", e);
+                        HttpResponse::InternalServerError().finish()
+                    }
+                };
+            }
+
+            // 4. Start a session
+            return start_session(pool.get_ref(), &req, user).await;
         }
     }
 
+    throttle::record_failure(pool.get_ref(), "login_failed", &body.username, ip.as_deref()).await;
     HttpResponse::Unauthorized().body("Invalid username or password")
 }
 
-pub async fn me(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        let user = query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-            .bind(&claims.sub)
-            .fetch_optional(pool.get_ref())
-            .await
-            .unwrap_or(None);
-
-        if let Some(user) = user {
-            HttpResponse::Ok().json(user)
-        } else {
-            HttpResponse::Unauthorized().finish()
+// Second login step: the challenge token from `login` plus a TOTP or
+// recovery code
+pub async fn verify_login(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<VerifyLoginRequest>,
+) -> impl Responder {
+    // Codes are throttled like passwords
+    let ip = session::client_ip(&req);
+    match two_factor::challenge_username(pool.get_ref(), &body.challenge_token).await {
+        Ok(Some(username)) => {
+            if let Err(resp) = throttle::check_login(pool.get_ref(), &username, ip.as_deref()).await {
+                return resp;
+            }
         }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("verify_login error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let user_id = match two_factor::redeem_challenge(pool.get_ref(), &body.challenge_token, &body.code).await {
+        Ok(Redeemed::User(id)) => id,
+        Ok(Redeemed::WrongCode(username)) => {
+            throttle::record_failure(pool.get_ref(), "two_factor_failed", &username, ip.as_deref()).await;
+            return HttpResponse::Unauthorized().body("Invalid code or expired challenge");
+        }
+        Ok(Redeemed::Invalid) => return HttpResponse::Unauthorized().body("Invalid code or expired challenge"),
+        Err(e) => {
+            eprintln!("verify_login error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let user = query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(&user_id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .unwrap_or(None);
+    match user {
+        Some(user) => start_session(pool.get_ref(), &req, user).await,
+        None => HttpResponse::Unauthorized().finish(),
+    }
+}
+
+// Signs in to the workspace the user joined first. Only a complete sign-in
+// (password and, with 2FA, code) starts the account's throttle over.
+async fn start_session(pool: &DbPool, req: &HttpRequest, user: User) -> HttpResponse {
+    let workspace = match session::default_workspace(pool, &user.id).await {
+        Ok(Some((workspace_id, _))) => load_member(pool, &workspace_id, &user.id).await.map(|u| u.map(|u| (workspace_id, u))),
+        Ok(None) => return HttpResponse::Forbidden().body("Not a member of any workspace"),
+        Err(e) => Err(e),
+    };
+    let (workspace_id, user) = match workspace {
+        Ok(Some(found)) => found,
+        Ok(None) => return HttpResponse::Unauthorized().finish(),
+        Err(e) => {
+            eprintln!("login session error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match session::create(pool, req, &user.id, &workspace_id, &user.role).await {
+        Ok(tokens) => {
+            if let Err(e) = throttle::clear_account(pool, &user.username).await {
+                eprintln!("login throttle error: {:?}", e);
+            }
+            audit::log(pool, &Actor::user(req, &user.id), "auth.login", "user", &user.id, None, None).await;
+            let two_factor_setup_required = !user.totp_enabled && two_factor::required_for(pool, &workspace_id, &user.role).await;
+            HttpResponse::Ok().json(AuthResponse { tokens, user, workspace_id, two_factor_setup_required })
+        }
+        Err(e) => {
+            eprintln!("login session error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// With the role and rate of the active workspace
+pub async fn me(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let (user_id, workspace_id) = match req.extensions().get::<AuthClaims>() {
+        Some(c) => (c.sub.clone(), c.wid.clone()),
+        None => return HttpResponse::Unauthorized().finish(),
+    };
+
+    let user = load_member(pool.get_ref(), &workspace_id, &user_id).await.unwrap_or(None);
+
+    if let Some(user) = user {
+        HttpResponse::Ok().json(user)
     } else {
         HttpResponse::Unauthorized().finish()
     }
 }
 
-fn generate_token(id: &str, role: &str) -> String {
-    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-    let expiration = Utc::now()
-        .checked_add_signed(chrono::Duration::days(7))
-        .expect("valid timestamp")
-        .timestamp() as usize;
-
-    let claims = AuthClaims {
-        sub: id.to_string(),
-        role: role.to_string(),
-        exp: expiration,
-    };
+pub async fn refresh(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<RefreshRequest>,
+) -> impl Responder {
+    match session::refresh(pool.get_ref(), &req, &body.refresh_token).await {
+        Ok(Some(tokens)) => HttpResponse::Ok().json(tokens),
+        Ok(None) => HttpResponse::Unauthorized().body("Invalid or expired refresh token"),
+        Err(e) => {
+            eprintln!("refresh error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// Ends the session of the bearer token (expired ones too) and/or the one
+// the given refresh token belongs to
+pub async fn logout(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: Option<web::Json<LogoutRequest>>,
+) -> impl Responder {
+    let claims = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|t| session::decode_access_token(t, true));
+    let refresh_token = body.and_then(|b| b.into_inner().refresh_token);
+    if claims.is_none() && refresh_token.is_none() {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    if let Some(claims) = claims {
+        if let Err(e) = session::revoke(pool.get_ref(), &claims.sid).await {
+            eprintln!("logout error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        audit::log(pool.get_ref(), &Actor::user(&req, &claims.sub), "auth.logout", "session", &claims.sid, None, None).await;
+    }
+    if let Some(token) = refresh_token {
+        if let Err(e) = session::revoke_by_refresh_token(pool.get_ref(), &token).await {
+            eprintln!("logout error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .expect("Token generation failed")
+    HttpResponse::Ok().finish()
 }
 
 pub async fn change_password(
@@ -158,6 +327,9 @@ pub async fn change_password(
     body: web::Json<crate::models::user::ChangePasswordRequest>,
 ) -> impl Responder {
     let claims = match req.extensions().get::<AuthClaims>() {
+        Some(c) if c.scope.is_some() => {
+            return HttpResponse::Forbidden().body("Password can only be changed from a signed-in session")
+        }
         Some(c) => c.sub.clone(), // ID
         None => return HttpResponse::Unauthorized().finish(),
     };
@@ -194,7 +366,10 @@ pub async fn change_password(
             .await;
 
         match result {
-            Ok(_) => HttpResponse::Ok().finish(),
+            Ok(_) => {
+                audit::log(pool.get_ref(), &Actor::of(&req), "user.password_change", "user", &claims, None, None).await;
+                HttpResponse::Ok().finish()
+            }
             Err(_) => HttpResponse::InternalServerError().finish(),
         }
     } else {
@@ -1,55 +1,83 @@
 use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::{Duration, Months};
 use sqlx::query_as;
 
+use crate::billing;
 use crate::db::DbPool;
 use crate::models::dashboard::*;
 use crate::models::user::AuthClaims;
+use crate::tz;
 
-pub async fn get_dashboard(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn get_dashboard(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<DashboardQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
 
-    let total_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE user_id = $1")
+    // Day and month buckets follow the user's local calendar
+    let zone = match tz::resolve(pool.get_ref(), &user_id, query.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let today = tz::today(zone);
+    let today_start = tz::start_of_day(zone, today);
+    let today_end = tz::start_of_day(zone, today + Duration::days(1));
+    let month_start = tz::start_of_day(zone, tz::first_of_month(today));
+    let month_end = tz::start_of_day(zone, tz::first_of_month(today) + Months::new(1));
+    let week_start = tz::start_of_day(zone, today - Duration::days(6));
+
+    let total_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE user_id = $1 AND workspace_id = $2")
         .bind(&user_id)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let completed_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'completed' AND user_id = $1")
+    let completed_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'completed' AND user_id = $1 AND workspace_id = $2")
         .bind(&user_id)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let pending_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'pending' AND user_id = $1")
+    let pending_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'pending' AND user_id = $1 AND workspace_id = $2")
         .bind(&user_id)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let in_progress_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'in_progress' AND user_id = $1")
+    let in_progress_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE status = 'in_progress' AND user_id = $1 AND workspace_id = $2")
         .bind(&user_id)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let total_minutes_today: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(duration_minutes), 0)::BIGINT FROM time_entries WHERE CAST(created_at AS DATE) = CURRENT_DATE AND user_id = $1")
+    let total_minutes_today: i64 = sqlx::query_scalar("SELECT ROUND(COALESCE(SUM(duration_seconds), 0) / 60.0)::BIGINT FROM time_entries WHERE user_id = $1 AND start_time >= $2 AND start_time < $3 AND task_id IN (SELECT id FROM tasks WHERE workspace_id = $4)")
         .bind(&user_id)
+        .bind(today_start)
+        .bind(today_end)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let total_minutes_month: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(duration_minutes), 0)::BIGINT FROM time_entries WHERE TO_CHAR(CAST(created_at AS DATE), 'YYYY-MM') = TO_CHAR(CURRENT_DATE, 'YYYY-MM') AND user_id = $1")
+    let total_minutes_month: i64 = sqlx::query_scalar("SELECT ROUND(COALESCE(SUM(duration_seconds), 0) / 60.0)::BIGINT FROM time_entries WHERE user_id = $1 AND start_time >= $2 AND start_time < $3 AND task_id IN (SELECT id FROM tasks WHERE workspace_id = $4)")
         .bind(&user_id)
+        .bind(month_start)
+        .bind(month_end)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
 
-    let total_entries_today: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM time_entries WHERE CAST(created_at AS DATE) = CURRENT_DATE AND user_id = $1")
+    let total_entries_today: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM time_entries WHERE user_id = $1 AND start_time >= $2 AND start_time < $3 AND task_id IN (SELECT id FROM tasks WHERE workspace_id = $4)")
         .bind(&user_id)
+        .bind(today_start)
+        .bind(today_end)
+        .bind(&workspace_id)
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or(0);
@@ -58,42 +86,55 @@ pub async fn get_dashboard(pool: web::Data<DbPool>, req: HttpRequest) -> impl Re
         "SELECT e.id as entry_id, e.task_id, t.title as task_title, e.duration_minutes, e.notes, e.created_at
          FROM time_entries e
          JOIN tasks t ON t.id = e.task_id
-         WHERE e.user_id = $1
+         WHERE e.user_id = $1 AND t.workspace_id = $2
          ORDER BY e.created_at DESC
          LIMIT 10"
     )
     .bind(&user_id)
+    .bind(&workspace_id)
     .fetch_all(pool.get_ref())
     .await
     .unwrap_or_default();
 
-    let project_stats: Vec<ProjectStat> = query_as(
-        "SELECT p.name, p.color, COUNT(DISTINCT t.id)::BIGINT as task_count, COALESCE(SUM(e.duration_minutes), 0)::BIGINT as total_minutes
+    let project_stats: Vec<ProjectStat> = query_as(&format!(
+        "SELECT p.name, p.color, COUNT(DISTINCT t.id)::BIGINT as task_count, ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT as total_minutes,
+                {} as billable_minutes, {} as amount
          FROM projects p
+         JOIN project_members pm ON pm.project_id = p.id AND pm.user_id = $1
          LEFT JOIN tasks t ON t.project_id = p.id
          LEFT JOIN time_entries e ON e.task_id = t.id
-         WHERE p.user_id = $1
+         WHERE p.workspace_id = $2
          GROUP BY p.id, p.name, p.color
-         ORDER BY total_minutes DESC"
-    )
+         ORDER BY total_minutes DESC",
+        billing::billable_minutes_sql("TRUE"),
+        billing::amount_sql("TRUE"),
+    ))
     .bind(&user_id)
+    .bind(&workspace_id)
     .fetch_all(pool.get_ref())
     .await
     .unwrap_or_default();
 
     let daily_minutes: Vec<DailyMinutes> = query_as(
-        "SELECT SUBSTRING(created_at FROM 1 FOR 10) as date, SUM(duration_minutes)::BIGINT as minutes
+        "SELECT CAST(start_time AT TIME ZONE $2 AS DATE) as date, ROUND(SUM(duration_seconds) / 60.0)::BIGINT as minutes
          FROM time_entries
-         WHERE CAST(created_at AS DATE) >= CURRENT_DATE - INTERVAL '6 days' AND user_id = $1
+         WHERE user_id = $1 AND start_time >= $3 AND start_time < $4
+           AND task_id IN (SELECT id FROM tasks WHERE workspace_id = $5)
          GROUP BY date
          ORDER BY date"
     )
     .bind(&user_id)
+    .bind(zone.name())
+    .bind(week_start)
+    .bind(today_end)
+    .bind(&workspace_id)
     .fetch_all(pool.get_ref())
     .await
     .unwrap_or_default();
 
     let summary = DashboardSummary {
+        timezone: zone.name().to_string(),
+        currency: billing::currency(pool.get_ref(), &workspace_id).await,
         total_tasks,
         completed_tasks,
         pending_tasks,
@@ -103,10 +144,7 @@ pub async fn get_dashboard(pool: web::Data<DbPool>, req: HttpRequest) -> impl Re
         total_entries_today,
         recent_entries,
         project_stats,
-        daily_minutes: daily_minutes.into_iter().map(|d| DailyMinutes {
-            date: d.date.or(Some("".to_string())),
-            minutes: d.minutes.or(Some(0)),
-        }).collect(),
+        daily_minutes,
     };
 
     HttpResponse::Ok().json(summary)
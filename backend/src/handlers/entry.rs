@@ -1,34 +1,108 @@
 use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
-use sqlx::{query_as, FromRow};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use sqlx::{query_as, Postgres, QueryBuilder};
 
+use crate::audit::{self, Actor};
+use crate::billing;
 use crate::db::DbPool;
+use crate::handlers::task;
+use crate::lock;
+use crate::models::entry::*;
+use crate::models::settings::LockOverrideQuery;
 use crate::models::user::AuthClaims;
+use crate::pagination::{self, csv, Cursor};
+use crate::rounding;
+use crate::tz;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct TimeEntry {
-    pub id: String,
-    pub task_id: String,
-    #[sqlx(default)]
-    pub task_title: Option<String>,
-    pub start_time: String,
-    pub end_time: Option<String>,
-    pub duration_minutes: i64,
-    pub notes: String,
-    pub created_at: String,
+const ENTRY_ORDER: &str = "start_time:desc";
+
+// Clients that work in whole minutes can't match a span to the second
+const SPAN_TOLERANCE_SECONDS: i64 = 60;
+
+// An entry of user $2 by id ($1), if its task is in workspace $3
+const ENTRY_IN_WORKSPACE: &str = "SELECT e.* FROM time_entries e JOIN tasks t ON t.id = e.task_id
+     WHERE e.id = $1 AND e.user_id = $2 AND t.workspace_id = $3";
+
+// Where an entry without end_time ends
+const ENTRY_END_SQL: &str = "COALESCE(e.end_time, e.start_time + e.duration_seconds * INTERVAL '1 second')";
+
+fn invalid(code: &'static str, message: impl Into<String>) -> HttpResponse {
+    HttpResponse::BadRequest().json(EntryValidationError { code, message: message.into(), conflicts: vec![] })
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateEntryRequest {
-    pub start_time: String,
-    pub end_time: Option<String>,
-    pub duration_minutes: i64,
-    pub notes: String,
+// Checks an entry as it would be stored. `exclude_id` is the entry itself
+// when updating; `timer_made` skips the span check, as rounding and pauses
+// make timer durations differ from their span on purpose.
+#[allow(clippy::too_many_arguments)]
+async fn validate_entry(
+    db: &DbPool,
+    user_id: &str,
+    exclude_id: Option<&str>,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    duration_seconds: i64,
+    timer_made: bool,
+    allow_overlap: bool,
+) -> Result<(), HttpResponse> {
+    if end.is_some_and(|end| end <= start) {
+        return Err(invalid("end_before_start", "end_time must be after start_time"));
+    }
+    if duration_seconds < 0 {
+        return Err(invalid("negative_duration", "duration must not be negative"));
+    }
+    if let Some(end) = end {
+        let span = (end - start).num_seconds();
+        if !timer_made && (duration_seconds - span).abs() >= SPAN_TOLERANCE_SECONDS {
+            return Err(invalid(
+                "duration_mismatch",
+                format!("duration ({}s) does not match the span between start_time and end_time ({}s)", duration_seconds, span),
+            ));
+        }
+    }
+    if allow_overlap {
+        return Ok(());
+    }
+
+    let end = end.unwrap_or(start + Duration::seconds(duration_seconds));
+    if end <= start {
+        return Ok(());
+    }
+    let conflicts = query_as::<_, EntryConflict>(&format!(
+        "SELECT 'entry' AS kind, e.id, e.task_id, t.title AS task_title, e.start_time, {end} AS end_time
+         FROM time_entries e JOIN tasks t ON t.id = e.task_id
+         WHERE e.user_id = $1 AND e.id <> $2 AND e.start_time < $4 AND {end} > $3
+         UNION ALL
+         SELECT 'timer', at.id, at.task_id, t.title, at.start_time, NOW()
+         FROM active_timers at JOIN tasks t ON t.id = at.task_id
+         WHERE at.user_id = $1 AND at.start_time < $4 AND NOW() > $3
+         ORDER BY start_time
+         LIMIT 20",
+        end = ENTRY_END_SQL
+    ))
+    .bind(user_id)
+    .bind(exclude_id.unwrap_or(""))
+    .bind(start)
+    .bind(end)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        eprintln!("validate_entry error: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(HttpResponse::Conflict().json(EntryValidationError {
+        code: "overlap",
+        message: "Entry overlaps existing time; pass allow_overlap to store it anyway".into(),
+        conflicts,
+    }))
 }
 
 pub async fn get_entries(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
@@ -37,11 +111,12 @@ pub async fn get_entries(pool: web::Data<DbPool>, req: HttpRequest, path: web::P
         "SELECT e.*, t.title as task_title 
          FROM time_entries e 
          JOIN tasks t ON e.task_id = t.id 
-         WHERE e.task_id = $1 AND e.user_id = $2
+         WHERE e.task_id = $1 AND e.user_id = $2 AND t.workspace_id = $3
          ORDER BY e.created_at DESC"
     )
     .bind(task_id)
     .bind(user_id)
+    .bind(workspace_id)
     .fetch_all(pool.get_ref())
     .await
     .unwrap_or_default();
@@ -49,50 +124,370 @@ pub async fn get_entries(pool: web::Data<DbPool>, req: HttpRequest, path: web::P
     HttpResponse::Ok().json(entries)
 }
 
-pub async fn create_entry(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<CreateEntryRequest>) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn create_entry(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<CreateTimeEntryRequest>, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let task_id = path.into_inner();
     let id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().to_rfc3339();
-    
-    sqlx::query("INSERT INTO time_entries (id, task_id, start_time, end_time, duration_minutes, notes, created_at, user_id) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)")
-        .bind(&id).bind(&task_id).bind(&body.start_time).bind(&body.end_time).bind(body.duration_minutes).bind(&body.notes).bind(&now).bind(&user_id)
-        .execute(pool.get_ref()).await.ok();
-        
+    let now = chrono::Utc::now();
+
+    let db = pool.get_ref();
+
+    if !task::trackable(db, &task_id, &user_id, &workspace_id).await {
+        return HttpResponse::NotFound().body("Task not found");
+    }
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+    let overridden = match lock::check(db, &workspace_id, &user_id, &[body.start_time], override_reason.as_deref()).await {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+
+    let duration_seconds = match (body.duration_seconds, body.duration_minutes, body.end_time) {
+        (Some(secs), _, _) => secs,
+        (None, Some(mins), _) => mins * 60,
+        (None, None, Some(end)) => (end - body.start_time).num_seconds(),
+        (None, None, None) => return HttpResponse::BadRequest().body("duration_seconds, duration_minutes or end_time is required"),
+    };
+    if let Err(resp) = validate_entry(db, &user_id, None, body.start_time, body.end_time, duration_seconds, false, body.allow_overlap).await {
+        return resp;
+    }
+
+    let sql = format!(
+        "INSERT INTO time_entries (id, task_id, start_time, end_time, duration_seconds, duration_minutes, notes, created_at, user_id, billable)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9, COALESCE($10, {}))",
+        billing::default_billable_sql("$2")
+    );
+    let inserted = sqlx::query(&sql)
+        .bind(&id).bind(&task_id).bind(body.start_time).bind(body.end_time).bind(duration_seconds).bind(rounding::to_minutes(duration_seconds)).bind(body.notes.clone().unwrap_or_default()).bind(now).bind(&user_id).bind(body.billable)
+        .execute(db).await;
+    if let Err(e) = inserted {
+        eprintln!("create_entry error: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    if let (Some(date), Some(reason)) = (overridden, override_reason.as_deref()) {
+        lock::log(db, &workspace_id, &user_id, "create_entry", Some(date), Some(&id), reason).await;
+    }
+
     HttpResponse::Created().json(serde_json::json!({"id": id}))
 }
 
-pub async fn delete_entry(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn delete_entry(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let id = path.into_inner();
-    sqlx::query("DELETE FROM time_entries WHERE id = $1 AND user_id = $2").bind(id).bind(user_id).execute(pool.get_ref()).await.ok();
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+
+    let existing = query_as::<_, TimeEntry>(ENTRY_IN_WORKSPACE)
+        .bind(&id).bind(&user_id).bind(&workspace_id).fetch_optional(pool.get_ref()).await.unwrap_or(None);
+    let mut overridden = None;
+    if let Some(ref entry) = existing {
+        if entry.invoice_id.is_some() {
+            return HttpResponse::Conflict().body("Entry is on an invoice");
+        }
+        overridden = match lock::check(pool.get_ref(), &workspace_id, &user_id, &[entry.start_time], override_reason.as_deref()).await {
+            Ok(d) => d,
+            Err(resp) => return resp,
+        };
+    }
+
+    let deleted = sqlx::query("DELETE FROM time_entries WHERE id = $1 AND user_id = $2 AND task_id IN (SELECT id FROM tasks WHERE workspace_id = $3)")
+        .bind(&id).bind(&user_id).bind(&workspace_id).execute(pool.get_ref()).await;
+    if deleted.is_ok_and(|r| r.rows_affected() > 0) {
+        audit::log(pool.get_ref(), &Actor::of(&req), "entry.delete", "entry", &id, existing.as_ref().and_then(audit::json), None).await;
+    }
+    if let (Some(date), Some(reason)) = (overridden, override_reason.as_deref()) {
+        lock::log(pool.get_ref(), &workspace_id, &user_id, "delete_entry", Some(date), Some(&id), reason).await;
+    }
     HttpResponse::Ok().json(serde_json::json!({"deleted": true}))
 }
 
-pub async fn get_all_entries(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn update_entry(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<UpdateTimeEntryRequest>, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
-    let entries: Vec<TimeEntry> = query_as(
-        "SELECT e.*, t.title as task_title 
-         FROM time_entries e 
-         JOIN tasks t ON e.task_id = t.id 
-         WHERE e.user_id = $1
-         ORDER BY e.created_at DESC"
+    let id = path.into_inner();
+    let db = pool.get_ref();
+
+    // Same ownership rule as delete_entry: only the user's own entries
+    let existing = query_as::<_, TimeEntry>(ENTRY_IN_WORKSPACE)
+        .bind(&id)
+        .bind(&user_id)
+        .bind(&workspace_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+
+    let existing = match existing {
+        Some(e) => e,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    if existing.invoice_id.is_some() {
+        return HttpResponse::Conflict().body("Entry is on an invoice");
+    }
+    let before = audit::json(&existing);
+
+    // Moving to another task requires being able to track on it too
+    if let Some(ref task_id) = body.task_id {
+        if !task::trackable(db, task_id, &user_id, &workspace_id).await {
+            return HttpResponse::NotFound().body("Task not found");
+        }
+    }
+
+    let task_id = body.task_id.clone().unwrap_or(existing.task_id);
+    let start_time = body.start_time.unwrap_or(existing.start_time);
+    let end_time = body.end_time.or(existing.end_time);
+    let notes = body.notes.clone().unwrap_or(existing.notes);
+    let billable = body.billable.unwrap_or(existing.billable);
+
+    // Neither the period it leaves nor the one it moves into may be locked
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+    let overridden = match lock::check(db, &workspace_id, &user_id, &[existing.start_time, start_time], override_reason.as_deref()).await {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+
+    // An explicit duration wins; otherwise moving a bound of a closed entry
+    // keeps the duration in step with the span
+    let duration_seconds = match (body.duration_seconds, body.duration_minutes, end_time) {
+        (Some(secs), _, _) => secs,
+        (None, Some(mins), _) => mins * 60,
+        (None, None, Some(end)) if body.start_time.is_some() || body.end_time.is_some() => (end - start_time).num_seconds(),
+        _ => existing.duration_seconds,
+    };
+    let timer_made = existing.raw_seconds.is_some() && body.duration_seconds.is_none() && body.duration_minutes.is_none();
+    if let Err(resp) = validate_entry(db, &user_id, Some(&id), start_time, end_time, duration_seconds, timer_made, body.allow_overlap).await {
+        return resp;
+    }
+
+    let result = sqlx::query(
+        "UPDATE time_entries SET task_id = $1, start_time = $2, end_time = $3, duration_seconds = $4, duration_minutes = $5,
+                notes = $6, billable = $7
+         WHERE id = $8 AND user_id = $9"
     )
-    .bind(user_id)
-    .fetch_all(pool.get_ref())
-    .await
-    .unwrap_or_default();
-    HttpResponse::Ok().json(entries)
+    .bind(&task_id).bind(start_time).bind(end_time).bind(duration_seconds).bind(rounding::to_minutes(duration_seconds))
+    .bind(&notes).bind(billable).bind(&id).bind(&user_id)
+    .execute(db)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("update_entry error: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    if let (Some(date), Some(reason)) = (overridden, override_reason.as_deref()) {
+        lock::log(db, &workspace_id, &user_id, "update_entry", Some(date), Some(&id), reason).await;
+    }
+
+    let entry = query_as::<_, TimeEntry>(
+        "SELECT e.*, t.title as task_title
+         FROM time_entries e
+         JOIN tasks t ON e.task_id = t.id
+         WHERE e.id = $1"
+    )
+    .bind(&id)
+    .fetch_one(db)
+    .await;
+
+    match entry {
+        Ok(e) => {
+            audit::log(db, &Actor::of(&req), "entry.update", "entry", &id, before, audit::json(&e)).await;
+            HttpResponse::Ok().json(e)
+        }
+        Err(_) => HttpResponse::NotFound().finish(),
+    }
+}
+
+pub async fn get_all_entries(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<EntryListQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    // Bare dates and day/week buckets are local to the user
+    let zone = match tz::resolve(db, &user_id, query.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let from = match query.from.as_deref().map(|v| tz::parse_bound(zone, v, false)).transpose() {
+        Ok(v) => v,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let to = match query.to.as_deref().map(|v| tz::parse_bound(zone, v, true)).transpose() {
+        Ok(v) => v,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+
+    let push_filters = |qb: &mut QueryBuilder<'_, Postgres>| {
+        qb.push(" WHERE e.user_id = ").push_bind(user_id.clone());
+        qb.push(" AND t.workspace_id = ").push_bind(workspace_id.clone());
+        if let Some(v) = from { qb.push(" AND e.start_time >= ").push_bind(v); }
+        if let Some(v) = to   { qb.push(" AND e.start_time < ").push_bind(v); }
+        if let Some(v) = csv(&query.task_id)    { qb.push(" AND e.task_id = ANY(").push_bind(v).push(")"); }
+        if let Some(v) = csv(&query.project_id) { qb.push(" AND t.project_id = ANY(").push_bind(v).push(")"); }
+        if let Some(v) = csv(&query.category)   { qb.push(" AND t.category = ANY(").push_bind(v).push(")"); }
+        if let Some(v) = query.billable         { qb.push(" AND e.billable = ").push_bind(v); }
+        if let Some(v) = csv(&query.tag_id) {
+            qb.push(" AND EXISTS (SELECT 1 FROM task_tags ft WHERE ft.task_id = e.task_id AND ft.tag_id = ANY(").push_bind(v).push("))");
+        }
+    };
+
+    // ─── Aggregated mode ───
+    if let Some(ref group_by) = query.group_by {
+        let mut qb = QueryBuilder::<Postgres>::new("SELECT ");
+        let order = match group_by.as_str() {
+            "day" => {
+                qb.push("TO_CHAR(e.start_time AT TIME ZONE ").push_bind(zone.name())
+                  .push(", 'YYYY-MM-DD') AS key, NULL::TEXT AS label");
+                "key"
+            }
+            "week" => {
+                qb.push("TO_CHAR(DATE_TRUNC('week', e.start_time AT TIME ZONE ").push_bind(zone.name())
+                  .push("), 'YYYY-MM-DD') AS key, NULL::TEXT AS label");
+                "key"
+            }
+            "task" => {
+                qb.push("e.task_id AS key, MAX(t.title) AS label");
+                "total_minutes DESC"
+            }
+            "project" => {
+                qb.push("t.project_id AS key, MAX(p.name) AS label");
+                "total_minutes DESC"
+            }
+            "tag" => {
+                qb.push("tg.id AS key, MAX(tg.name) AS label");
+                "total_minutes DESC"
+            }
+            other => return HttpResponse::BadRequest().body(format!("Unknown group_by: {}", other)),
+        };
+        qb.push(
+            ", ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT AS total_minutes, COUNT(e.id)::BIGINT AS entry_count
+             FROM time_entries e JOIN tasks t ON e.task_id = t.id LEFT JOIN projects p ON p.id = t.project_id",
+        );
+        if group_by == "tag" {
+            qb.push(" LEFT JOIN task_tags tt ON tt.task_id = t.id LEFT JOIN tags tg ON tg.id = tt.tag_id");
+        }
+        push_filters(&mut qb);
+        qb.push(format!(" GROUP BY 1 ORDER BY {}", order));
+
+        return match qb.build_query_as::<EntryGroupTotal>().fetch_all(db).await {
+            Ok(rows) => HttpResponse::Ok().json(rows),
+            Err(e) => {
+                eprintln!("get_all_entries group error: {:?}", e);
+                HttpResponse::InternalServerError().finish()
+            }
+        };
+    }
+
+    // ─── Paged list, newest work first ───
+    let limit = match pagination::parse_limit(query.limit) {
+        Ok(l) => l,
+        Err(resp) => return resp,
+    };
+    let cursor = match query.cursor.as_deref().filter(|t| !t.is_empty()) {
+        Some(token) => match Cursor::decode(token) {
+            Some(c) if c.order == ENTRY_ORDER => Some(c),
+            _ => return HttpResponse::BadRequest().body("Invalid cursor"),
+        },
+        None => None,
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(
+        "SELECT e.*, t.title as task_title FROM time_entries e JOIN tasks t ON e.task_id = t.id LEFT JOIN projects p ON p.id = t.project_id",
+    );
+    push_filters(&mut qb);
+    if let Some(c) = cursor {
+        qb.push(" AND (e.start_time, e.id) < (").push_bind(c.value).push("::timestamptz, ").push_bind(c.id).push(")");
+    }
+    qb.push(" ORDER BY e.start_time DESC, e.id DESC");
+    if let Some(l) = limit {
+        qb.push(" LIMIT ").push_bind(l + 1);
+    }
+
+    match qb.build_query_as::<TimeEntry>().fetch_all(db).await {
+        Ok(entries) => pagination::page_response(entries, limit, |e| Cursor {
+            order: ENTRY_ORDER.to_string(),
+            value: e.start_time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            id: e.id.clone(),
+        }),
+        Err(e) => {
+            eprintln!("get_all_entries error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── GET /api/entries/overlaps ───
+
+// Pairs of the user's entries that overlap, for cleaning up old data. Each
+// pair is listed once; `from`/`to` filter on start_time like get_all_entries.
+pub async fn get_entry_overlaps(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<OverlapQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    let zone = match tz::resolve(db, &user_id, query.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let from = match query.from.as_deref().map(|v| tz::parse_bound(zone, v, false)).transpose() {
+        Ok(v) => v,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let to = match query.to.as_deref().map(|v| tz::parse_bound(zone, v, true)).transpose() {
+        Ok(v) => v,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "WITH spans AS (
+             SELECT e.id, e.task_id, t.title, e.start_time, {} AS end_time
+             FROM time_entries e JOIN tasks t ON t.id = e.task_id
+             WHERE e.user_id = ",
+        ENTRY_END_SQL
+    ));
+    qb.push_bind(user_id);
+    qb.push(" AND t.workspace_id = ").push_bind(workspace_id);
+    if let Some(v) = from { qb.push(" AND e.start_time >= ").push_bind(v); }
+    if let Some(v) = to   { qb.push(" AND e.start_time < ").push_bind(v); }
+    qb.push(
+        ")
+         SELECT a.id AS entry_id, a.task_id, a.title AS task_title, a.start_time, a.end_time,
+                b.id AS other_entry_id, b.task_id AS other_task_id, b.title AS other_task_title,
+                b.start_time AS other_start_time, b.end_time AS other_end_time,
+                EXTRACT(EPOCH FROM LEAST(a.end_time, b.end_time) - GREATEST(a.start_time, b.start_time))::BIGINT AS overlap_seconds
+         FROM spans a
+         JOIN spans b ON a.id < b.id AND a.start_time < b.end_time AND b.start_time < a.end_time
+         ORDER BY a.start_time, b.start_time
+         LIMIT ",
+    );
+    qb.push_bind(pagination::MAX_LIMIT);
+
+    match qb.build_query_as::<EntryOverlap>().fetch_all(db).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_entry_overlaps error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
 }
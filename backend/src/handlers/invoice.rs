@@ -0,0 +1,347 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{query, query_as, query_scalar, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::billing;
+use crate::db::DbPool;
+use crate::invoice_doc;
+use crate::models::invoice::*;
+use crate::models::user::AuthClaims;
+use crate::pagination::csv;
+use crate::tz;
+
+const INVOICE_STATUSES: [&str; 3] = ["draft", "sent", "paid"];
+
+// Paid is final; a sent invoice can go back to draft to be corrected
+fn can_transition(from: &str, to: &str) -> bool {
+    matches!((from, to), ("draft", "sent") | ("sent", "draft") | ("sent", "paid"))
+}
+
+async fn load_detail(pool: &DbPool, id: &str, user_id: &str, workspace_id: &str) -> Result<Option<InvoiceDetail>, sqlx::Error> {
+    let invoice = query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
+        .bind(id)
+        .bind(user_id)
+        .bind(workspace_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some(invoice) = invoice else { return Ok(None) };
+
+    let lines = query_as::<_, InvoiceLine>(
+        "SELECT line_no, task_id, description, minutes, hourly_rate_cents, amount
+         FROM invoice_lines WHERE invoice_id = $1 ORDER BY line_no"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(InvoiceDetail { invoice, lines }))
+}
+
+// ─── GET /api/invoices ───
+
+pub async fn get_invoices(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<InvoiceListQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new("SELECT * FROM invoices WHERE user_id = ");
+    qb.push_bind(user_id);
+    qb.push(" AND workspace_id = ").push_bind(workspace_id);
+    if let Some(v) = csv(&query.status)     { qb.push(" AND status = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.project_id) { qb.push(" AND project_id = ANY(").push_bind(v).push(")"); }
+    qb.push(" ORDER BY created_at DESC");
+
+    match qb.build_query_as::<Invoice>().fetch_all(pool.get_ref()).await {
+        Ok(invoices) => HttpResponse::Ok().json(invoices),
+        Err(e) => {
+            eprintln!("get_invoices error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/invoices ───
+
+pub async fn create_invoice(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<CreateInvoiceRequest>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    let zone = match tz::resolve(db, &user_id, body.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let (from, to) = match (tz::parse_bound(zone, &body.from, false), tz::parse_bound(zone, &body.to, true)) {
+        (Ok(f), Ok(t)) => (f, t),
+        (Err(msg), _) | (_, Err(msg)) => return HttpResponse::BadRequest().body(msg),
+    };
+    if to <= from {
+        return HttpResponse::BadRequest().body("to must be after from");
+    }
+
+    // Owners bill a project, for the time every member logged on it
+    let project_name: Option<String> = query_scalar(
+        "SELECT p.name FROM projects p JOIN project_members pm ON pm.project_id = p.id
+         WHERE p.id = $1 AND pm.user_id = $2 AND pm.role = 'owner' AND p.workspace_id = $3"
+    )
+    .bind(&body.project_id)
+    .bind(&user_id)
+    .bind(&workspace_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+    let Some(project_name) = project_name else {
+        return HttpResponse::NotFound().body("Project not found");
+    };
+    let client_name = body.client_name.as_deref().map(str::trim).filter(|n| !n.is_empty())
+        .map(String::from)
+        .unwrap_or(project_name);
+
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let currency = billing::currency(db, &workspace_id).await;
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+
+        // The real number is assigned once entries were claimed, so an empty
+        // range doesn't burn one
+        query(
+            "INSERT INTO invoices (id, number, user_id, project_id, client_name, status, currency,
+                                   period_start, period_end, timezone, notes, created_at, updated_at, workspace_id)
+             VALUES ($1, $1, $2, $3, $4, 'draft', $5, $6, $7, $8, $9, $10, $10, $11)"
+        )
+        .bind(&id)
+        .bind(&user_id)
+        .bind(&body.project_id)
+        .bind(&client_name)
+        .bind(&currency)
+        .bind(from)
+        .bind(to)
+        .bind(zone.name())
+        .bind(body.notes.clone().unwrap_or_default())
+        .bind(now)
+        .bind(&workspace_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Claiming is a single UPDATE, so concurrent invoices for the same
+        // range can't both take an entry
+        let claimed = query(
+            "UPDATE time_entries e SET invoice_id = $1
+             FROM tasks t
+             WHERE t.id = e.task_id AND t.project_id = $2
+               AND e.billable AND e.invoice_id IS NULL
+               AND e.start_time >= $3 AND e.start_time < $4"
+        )
+        .bind(&id)
+        .bind(&body.project_id)
+        .bind(from)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if claimed == 0 {
+            return Ok(false);
+        }
+
+        query(&format!(
+            "INSERT INTO invoice_lines (invoice_id, line_no, task_id, description, minutes, hourly_rate_cents, amount)
+             SELECT $1, ROW_NUMBER() OVER (ORDER BY MIN(x.title), x.task_id, x.rate)::INT,
+                    x.task_id, MIN(x.title), ROUND(SUM(x.duration_seconds) / 60.0)::BIGINT, x.rate,
+                    ROUND(SUM(x.duration_seconds) * x.rate / 3600.0)::BIGINT
+             FROM (
+                 SELECT e.task_id, t.title, e.duration_seconds, {} AS rate
+                 FROM time_entries e
+                 JOIN tasks t ON t.id = e.task_id
+                 LEFT JOIN projects p ON p.id = t.project_id
+                 WHERE e.invoice_id = $1
+             ) x
+             GROUP BY x.task_id, x.rate",
+            billing::ENTRY_RATE_SQL
+        ))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+        let seq: i64 = query_scalar("SELECT nextval('invoice_number_seq')")
+            .fetch_one(&mut *tx)
+            .await?;
+        query(
+            "UPDATE invoices SET number = $2,
+                 total = (SELECT COALESCE(SUM(amount), 0) FROM invoice_lines WHERE invoice_id = $1)
+             WHERE id = $1"
+        )
+        .bind(&id)
+        .bind(format!("INV-{:05}", seq))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::BadRequest().body("No unbilled billable entries in this range"),
+        Err(e) => {
+            eprintln!("create_invoice error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match load_detail(db, &id, &user_id, &workspace_id).await {
+        Ok(Some(detail)) => HttpResponse::Created().json(detail),
+        _ => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── GET /api/invoices/{id} ───
+
+pub async fn get_invoice(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    match load_detail(pool.get_ref(), &path.into_inner(), &user_id, &workspace_id).await {
+        Ok(Some(detail)) => HttpResponse::Ok().json(detail),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── GET /api/invoices/{id}/document?format=html|pdf ───
+
+pub async fn get_invoice_document(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<InvoiceDocumentQuery>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let detail = match load_detail(pool.get_ref(), &path.into_inner(), &user_id, &workspace_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    match query.format.as_deref().unwrap_or("html") {
+        "html" => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(invoice_doc::html(&detail)),
+        "pdf" => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!("inline; filename=\"{}.pdf\"", detail.invoice.number),
+            ))
+            .body(invoice_doc::pdf(&detail)),
+        other => HttpResponse::BadRequest().body(format!("Unknown format: {}", other)),
+    }
+}
+
+// ─── PUT /api/invoices/{id}/status ───
+
+pub async fn update_invoice_status(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<UpdateInvoiceStatusRequest>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let id = path.into_inner();
+    let db = pool.get_ref();
+
+    if !INVOICE_STATUSES.contains(&body.status.as_str()) {
+        return HttpResponse::BadRequest().body("status must be one of draft, sent, paid");
+    }
+
+    let current: Option<String> = query_scalar("SELECT status FROM invoices WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
+        .bind(&id)
+        .bind(&user_id)
+        .bind(&workspace_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    let Some(current) = current else {
+        return HttpResponse::NotFound().finish();
+    };
+    if current != body.status && !can_transition(&current, &body.status) {
+        return HttpResponse::Conflict().body(format!("Cannot change invoice from {} to {}", current, body.status));
+    }
+
+    // Guard on the old status in case it changed in between
+    let result = query("UPDATE invoices SET status = $1, updated_at = $2 WHERE id = $3 AND status = $4")
+        .bind(&body.status)
+        .bind(Utc::now())
+        .bind(&id)
+        .bind(&current)
+        .execute(db)
+        .await;
+    if let Err(e) = result {
+        eprintln!("update_invoice_status error: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    match load_detail(db, &id, &user_id, &workspace_id).await {
+        Ok(Some(detail)) => HttpResponse::Ok().json(detail),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── DELETE /api/invoices/{id} ───
+
+pub async fn delete_invoice(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let id = path.into_inner();
+    let db = pool.get_ref();
+
+    // Only drafts; their entries become billable again (ON DELETE SET NULL)
+    let result = query("DELETE FROM invoices WHERE id = $1 AND user_id = $2 AND workspace_id = $3 AND status = 'draft'")
+        .bind(&id)
+        .bind(&user_id)
+        .bind(&workspace_id)
+        .execute(db)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(serde_json::json!({"deleted": true})),
+        Ok(_) => {
+            let exists = query("SELECT id FROM invoices WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
+                .bind(&id).bind(&user_id).bind(&workspace_id).fetch_optional(db).await.unwrap_or(None);
+            if exists.is_some() {
+                HttpResponse::Conflict().body("Only draft invoices can be deleted")
+            } else {
+                HttpResponse::NotFound().finish()
+            }
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -6,3 +6,14 @@ pub mod task;
 pub mod timer;
 pub mod auth;
 pub mod user;
+pub mod tag;
+pub mod settings;
+pub mod invoice;
+pub mod timesheet;
+pub mod session;
+pub mod token;
+pub mod two_factor;
+pub mod throttle;
+pub mod role;
+pub mod audit;
+pub mod workspace;
@@ -1,37 +1,82 @@
 use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
 use chrono::Utc;
 use uuid::Uuid;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, query_scalar};
 
+use crate::audit::{self, Actor};
 use crate::db::DbPool;
 use crate::models::project::*;
 use crate::models::user::AuthClaims;
+use crate::rounding;
+
+const PROJECT_ROLES: [&str; 3] = ["owner", "editor", "viewer"];
+
+const MEMBER_SELECT: &str = "SELECT pm.user_id, u.username, u.full_name, pm.role, pm.created_at
+     FROM project_members pm
+     JOIN users u ON u.id = pm.user_id";
+
+// The user's role in a project of the active workspace, if they are a
+// member. Owners manage the project and its members, editors add tasks and
+// track time, viewers only look.
+pub async fn member_role(pool: &DbPool, project_id: &str, user_id: &str, workspace_id: &str) -> Option<String> {
+    query_scalar(
+        "SELECT pm.role FROM project_members pm JOIN projects p ON p.id = pm.project_id
+         WHERE pm.project_id = $1 AND pm.user_id = $2 AND p.workspace_id = $3"
+    )
+    .bind(project_id)
+    .bind(user_id)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None)
+}
+
+async fn require_owner(pool: &DbPool, project_id: &str, user_id: &str, workspace_id: &str) -> Result<(), HttpResponse> {
+    match member_role(pool, project_id, user_id, workspace_id).await.as_deref() {
+        None => Err(HttpResponse::NotFound().finish()),
+        Some("owner") => Ok(()),
+        Some(_) => Err(HttpResponse::Forbidden().body("Only project owners can do this")),
+    }
+}
+
+async fn fetch_member(pool: &DbPool, project_id: &str, user_id: &str) -> Result<ProjectMember, sqlx::Error> {
+    query_as::<_, ProjectMember>(&format!("{} WHERE pm.project_id = $1 AND pm.user_id = $2", MEMBER_SELECT))
+        .bind(project_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+}
 
 // ─── GET /api/projects ───
 
+// Projects the user is a member of; counts and time cover every member
+
 pub async fn get_projects(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
 
     let result = query_as::<_, ProjectWithStats>(
-        "SELECT p.id, p.name, p.color, p.description, p.created_at,
+        "SELECT p.id, p.name, p.color, p.description, p.created_at, p.billable, p.hourly_rate_cents,
+                p.rounding_mode, p.rounding_step_minutes, p.rounding_min_seconds, pm.role,
                 COALESCE(COUNT(t.id), 0)::BIGINT AS task_count,
                 COALESCE(SUM(CASE WHEN t.status = 'pending' THEN 1 ELSE 0 END), 0)::BIGINT AS pending_count,
                 COALESCE(SUM(CASE WHEN t.status = 'in_progress' THEN 1 ELSE 0 END), 0)::BIGINT AS in_progress_count,
                 COALESCE(SUM(CASE WHEN t.status = 'completed' THEN 1 ELSE 0 END), 0)::BIGINT AS completed_count,
-                COALESCE((SELECT SUM(e.duration_minutes) FROM time_entries e
-                          INNER JOIN tasks t2 ON t2.id = e.task_id
-                          WHERE t2.project_id = p.id), 0)::BIGINT AS total_minutes
+                ROUND(COALESCE((SELECT SUM(e.duration_seconds) FROM time_entries e
+                                INNER JOIN tasks t2 ON t2.id = e.task_id
+                                WHERE t2.project_id = p.id), 0) / 60.0)::BIGINT AS total_minutes
          FROM projects p
+         JOIN project_members pm ON pm.project_id = p.id AND pm.user_id = $1
          LEFT JOIN tasks t ON t.project_id = p.id
-         WHERE p.user_id = $1
-         GROUP BY p.id, p.name, p.color, p.description, p.created_at
+         WHERE p.workspace_id = $2
+         GROUP BY p.id, pm.role
          ORDER BY p.created_at ASC"
     )
     .bind(&user_id)
+    .bind(&workspace_id)
     .fetch_all(pool.get_ref())
     .await;
 
@@ -48,27 +93,54 @@ pub async fn create_project(
     req: HttpRequest,
     body: web::Json<CreateProjectRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
 
     let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    let now = Utc::now();
     let color = body.color.clone().unwrap_or_else(|| "#3b82f6".into());
     let desc = body.description.clone().unwrap_or_default();
+    let billable = body.billable.unwrap_or(false);
 
-    let result = query(
-        "INSERT INTO projects (id, name, color, description, created_at, user_id) VALUES ($1, $2, $3, $4, $5, $6)"
-    )
-    .bind(&id)
-    .bind(&body.name)
-    .bind(&color)
-    .bind(&desc)
-    .bind(&now)
-    .bind(&user_id)
-    .execute(pool.get_ref())
+    if body.hourly_rate_cents.is_some_and(|r| r < 0) {
+        return HttpResponse::BadRequest().body("hourly_rate_cents must not be negative");
+    }
+    if let Err(msg) = rounding::validate(body.rounding_mode.as_deref(), body.rounding_step_minutes, body.rounding_min_seconds) {
+        return HttpResponse::BadRequest().body(msg);
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        query(
+            "INSERT INTO projects (id, name, color, description, created_at, user_id, billable, hourly_rate_cents,
+                                   rounding_mode, rounding_step_minutes, rounding_min_seconds, workspace_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
+        )
+        .bind(&id)
+        .bind(&body.name)
+        .bind(&color)
+        .bind(&desc)
+        .bind(now)
+        .bind(&user_id)
+        .bind(billable)
+        .bind(body.hourly_rate_cents)
+        .bind(&body.rounding_mode)
+        .bind(body.rounding_step_minutes)
+        .bind(body.rounding_min_seconds)
+        .bind(&workspace_id)
+        .execute(&mut *tx)
+        .await?;
+        query("INSERT INTO project_members (project_id, user_id, role, created_at) VALUES ($1, $2, 'owner', $3)")
+            .bind(&id)
+            .bind(&user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
     .await;
 
     match result {
@@ -80,6 +152,11 @@ pub async fn create_project(
                 description: desc,
                 created_at: now,
                 user_id,
+                billable,
+                hourly_rate_cents: body.hourly_rate_cents,
+                rounding_mode: body.rounding_mode.clone(),
+                rounding_step_minutes: body.rounding_step_minutes,
+                rounding_min_seconds: body.rounding_min_seconds,
             };
             HttpResponse::Created().json(project)
         }
@@ -95,34 +172,77 @@ pub async fn update_project(
     path: web::Path<String>,
     body: web::Json<UpdateProjectRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let project_id = path.into_inner();
 
+    if let Some(Some(rate)) = body.hourly_rate_cents {
+        if rate < 0 {
+            return HttpResponse::BadRequest().body("hourly_rate_cents must not be negative");
+        }
+    }
+    if let Err(msg) = rounding::validate(
+        body.rounding_mode.clone().flatten().as_deref(),
+        body.rounding_step_minutes.flatten(),
+        body.rounding_min_seconds.flatten(),
+    ) {
+        return HttpResponse::BadRequest().body(msg);
+    }
+    if let Err(resp) = require_owner(pool.get_ref(), &project_id, &user_id, &workspace_id).await {
+        return resp;
+    }
+
     if let Some(ref name) = body.name {
-        query("UPDATE projects SET name = $1 WHERE id = $2 AND user_id = $3")
-            .bind(name).bind(&project_id).bind(&user_id)
+        query("UPDATE projects SET name = $1 WHERE id = $2")
+            .bind(name).bind(&project_id)
             .execute(pool.get_ref()).await.ok();
     }
     if let Some(ref color) = body.color {
-        query("UPDATE projects SET color = $1 WHERE id = $2 AND user_id = $3")
-            .bind(color).bind(&project_id).bind(&user_id)
+        query("UPDATE projects SET color = $1 WHERE id = $2")
+            .bind(color).bind(&project_id)
             .execute(pool.get_ref()).await.ok();
     }
     if let Some(ref desc) = body.description {
-        query("UPDATE projects SET description = $1 WHERE id = $2 AND user_id = $3")
-            .bind(desc).bind(&project_id).bind(&user_id)
+        query("UPDATE projects SET description = $1 WHERE id = $2")
+            .bind(desc).bind(&project_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+    // Only future entries pick up a changed billable flag
+    if let Some(billable) = body.billable {
+        query("UPDATE projects SET billable = $1 WHERE id = $2")
+            .bind(billable).bind(&project_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+    if let Some(rate) = body.hourly_rate_cents {
+        query("UPDATE projects SET hourly_rate_cents = $1 WHERE id = $2")
+            .bind(rate).bind(&project_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+    if let Some(ref mode) = body.rounding_mode {
+        query("UPDATE projects SET rounding_mode = $1 WHERE id = $2")
+            .bind(mode).bind(&project_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+    if let Some(step) = body.rounding_step_minutes {
+        query("UPDATE projects SET rounding_step_minutes = $1 WHERE id = $2")
+            .bind(step).bind(&project_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+    if let Some(min) = body.rounding_min_seconds {
+        query("UPDATE projects SET rounding_min_seconds = $1 WHERE id = $2")
+            .bind(min).bind(&project_id)
             .execute(pool.get_ref()).await.ok();
     }
 
     let project = query_as::<_, Project>(
-        "SELECT id, name, color, description, created_at, user_id FROM projects WHERE id = $1 AND user_id = $2"
+        "SELECT id, name, color, description, created_at, user_id, billable, hourly_rate_cents,
+                rounding_mode, rounding_step_minutes, rounding_min_seconds
+         FROM projects WHERE id = $1"
     )
     .bind(&project_id)
-    .bind(&user_id)
     .fetch_one(pool.get_ref())
     .await;
 
@@ -139,28 +259,20 @@ pub async fn delete_project(
     req: HttpRequest,
     path: web::Path<String>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let project_id = path.into_inner();
+    if let Err(resp) = require_owner(pool.get_ref(), &project_id, &user_id, &workspace_id).await {
+        return resp;
+    }
 
-    // Move tasks from this project to default
-    // TODO: Need user's default project or set to NULL?
-    // Setting to NULL for now as "default" ID might not belong to this user if we are strict.
-    // If we want a "Default" project for every user, we need to create it.
-    // Let's just set to NULL for now or delete them? No, keep tasks.
-    query("UPDATE tasks SET project_id = NULL WHERE project_id = $1 AND user_id = $2")
-        .bind(&project_id)
-        .bind(&user_id)
-        .execute(pool.get_ref())
-        .await
-        .ok();
-
-    let result = query("DELETE FROM projects WHERE id = $1 AND user_id = $2")
+    // Tasks stay, without a project (tasks.project_id is ON DELETE SET NULL),
+    // and so do the members' entries on them
+    let result = query("DELETE FROM projects WHERE id = $1")
         .bind(&project_id)
-        .bind(&user_id)
         .execute(pool.get_ref())
         .await;
 
@@ -175,3 +287,211 @@ pub async fn delete_project(
         Err(_) => HttpResponse::InternalServerError().finish()
     }
 }
+
+// ─── GET /api/projects/{id}/members ───
+
+pub async fn get_members(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let project_id = path.into_inner();
+    if member_role(pool.get_ref(), &project_id, &user_id, &workspace_id).await.is_none() {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let members = query_as::<_, ProjectMember>(&format!("{} WHERE pm.project_id = $1 ORDER BY pm.created_at", MEMBER_SELECT))
+        .bind(&project_id)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match members {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(e) => {
+            eprintln!("get_members error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/projects/{id}/members ───
+
+// Owners invite other members of the workspace
+pub async fn add_member(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<AddProjectMemberRequest>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let project_id = path.into_inner();
+    let db = pool.get_ref();
+
+    let role = body.role.as_deref().unwrap_or("editor");
+    if !PROJECT_ROLES.contains(&role) {
+        return HttpResponse::BadRequest().body("role must be one of owner, editor, viewer");
+    }
+    if let Err(resp) = require_owner(db, &project_id, &user_id, &workspace_id).await {
+        return resp;
+    }
+
+    let member_id: Option<String> = query_scalar(
+        "SELECT u.id FROM users u JOIN workspace_members m ON m.user_id = u.id
+         WHERE u.username = $1 AND m.workspace_id = $2"
+    )
+    .bind(body.username.trim())
+    .bind(&workspace_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+    let Some(member_id) = member_id else {
+        return HttpResponse::NotFound().body("User not found in this workspace");
+    };
+
+    let added = query("INSERT INTO project_members (project_id, user_id, role) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+        .bind(&project_id)
+        .bind(&member_id)
+        .bind(role)
+        .execute(db)
+        .await;
+    match added {
+        Ok(res) if res.rows_affected() == 0 => return HttpResponse::Conflict().body("User is already a member"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("add_project_member error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match fetch_member(db, &project_id, &member_id).await {
+        Ok(member) => {
+            audit::log(db, &Actor::of(&req), "project.member_add", "project", &project_id, None, audit::json(&member)).await;
+            HttpResponse::Created().json(member)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── PUT /api/projects/{id}/members/{user_id} ───
+
+pub async fn update_member(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<UpdateProjectMemberRequest>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let (project_id, member_id) = path.into_inner();
+    let db = pool.get_ref();
+
+    if !PROJECT_ROLES.contains(&body.role.as_str()) {
+        return HttpResponse::BadRequest().body("role must be one of owner, editor, viewer");
+    }
+    if let Err(resp) = require_owner(db, &project_id, &user_id, &workspace_id).await {
+        return resp;
+    }
+    let Ok(before) = fetch_member(db, &project_id, &member_id).await else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    // A project always keeps an owner
+    let updated = query(
+        "UPDATE project_members SET role = $1
+         WHERE project_id = $2 AND user_id = $3
+           AND ($1 = 'owner' OR role <> 'owner'
+                OR (SELECT COUNT(*) FROM project_members WHERE project_id = $2 AND role = 'owner') > 1)"
+    )
+    .bind(&body.role)
+    .bind(&project_id)
+    .bind(&member_id)
+    .execute(db)
+    .await;
+    match updated {
+        Ok(res) if res.rows_affected() == 0 => return HttpResponse::Conflict().body("A project needs at least one owner"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("update_project_member error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match fetch_member(db, &project_id, &member_id).await {
+        Ok(member) => {
+            audit::log(db, &Actor::of(&req), "project.member_update", "project", &project_id, audit::json(&before), audit::json(&member)).await;
+            HttpResponse::Ok().json(member)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── DELETE /api/projects/{id}/members/{user_id} ───
+
+// Owners remove members; anyone can leave. Tasks and entries the member
+// added stay with the project.
+pub async fn remove_member(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let (project_id, member_id) = path.into_inner();
+    let db = pool.get_ref();
+
+    if member_id != user_id {
+        if let Err(resp) = require_owner(db, &project_id, &user_id, &workspace_id).await {
+            return resp;
+        }
+    }
+    if member_role(db, &project_id, &member_id, &workspace_id).await.is_none() {
+        return HttpResponse::NotFound().finish();
+    }
+    let Ok(before) = fetch_member(db, &project_id, &member_id).await else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    // Tasks of the project assigned to them, but not their own, go back
+    // to being unassigned
+    let removed: Result<u64, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        let res = query(
+            "DELETE FROM project_members
+             WHERE project_id = $1 AND user_id = $2
+               AND (role <> 'owner' OR (SELECT COUNT(*) FROM project_members WHERE project_id = $1 AND role = 'owner') > 1)"
+        )
+        .bind(&project_id)
+        .bind(&member_id)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Ok(0);
+        }
+        query("UPDATE tasks SET assignee_id = NULL WHERE project_id = $1 AND assignee_id = $2 AND user_id <> $2")
+            .bind(&project_id)
+            .bind(&member_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(res.rows_affected())
+    }
+    .await;
+    match removed {
+        Ok(0) => HttpResponse::Conflict().body("A project needs at least one owner"),
+        Ok(_) => {
+            audit::log(db, &Actor::of(&req), "project.member_remove", "project", &project_id, audit::json(&before), None).await;
+            HttpResponse::Ok().json(serde_json::json!({"removed": true}))
+        }
+        Err(e) => {
+            eprintln!("remove_project_member error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
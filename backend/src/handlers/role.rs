@@ -0,0 +1,261 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use sqlx::{query, query_as, query_scalar};
+
+use crate::audit::{self, Actor};
+use crate::db::DbPool;
+use crate::models::role::*;
+use crate::rbac::{self, Grants};
+
+// Roles visible in workspace $1: the built-in ones and its own
+const ROLE_SELECT: &str = "SELECT r.name, r.description, r.level, r.builtin,
+            ARRAY(SELECT permission FROM role_permissions WHERE role_id = r.id ORDER BY permission) AS permissions,
+            (SELECT COUNT(*) FROM workspace_members m WHERE m.workspace_id = $1 AND m.role = r.name) AS user_count,
+            r.created_at
+     FROM roles r
+     WHERE (r.workspace_id IS NULL OR r.workspace_id = $1)";
+
+fn caller(req: &HttpRequest) -> Result<Grants, HttpResponse> {
+    rbac::grants(req).ok_or_else(|| HttpResponse::Unauthorized().finish())
+}
+
+// Nobody can hand out more than they hold, or rank a role up to their own
+// level
+fn check_definition(grants: &Grants, level: Option<i32>, permissions: Option<&[String]>) -> Result<(), HttpResponse> {
+    if let Some(level) = level {
+        if level < 0 || level >= grants.level {
+            return Err(HttpResponse::BadRequest().body(format!("level must be between 0 and {}", grants.level - 1)));
+        }
+    }
+    for p in permissions.unwrap_or_default() {
+        if !rbac::ALL.contains(&p.as_str()) {
+            return Err(HttpResponse::BadRequest().body(format!("Unknown permission: {}", p)));
+        }
+        if !grants.has(p) {
+            return Err(HttpResponse::Forbidden().body(format!("Cannot grant {} without holding it", p)));
+        }
+    }
+    Ok(())
+}
+
+// Whether the caller may edit the role: only their workspace's own roles,
+// below their own level. Built-in roles are shared by every workspace, so
+// none of them can change them.
+async fn editable(pool: &DbPool, grants: &Grants, name: &str) -> Result<(), HttpResponse> {
+    let role: Option<(i32, bool)> = query_as(
+        "SELECT level, builtin FROM roles WHERE name = $1 AND (workspace_id IS NULL OR workspace_id = $2)"
+    )
+    .bind(name)
+    .bind(&grants.workspace_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    match role {
+        None => Err(HttpResponse::NotFound().finish()),
+        Some((_, true)) => Err(HttpResponse::BadRequest().body("Built-in roles are shared by all workspaces and cannot be edited")),
+        Some((level, _)) if level >= grants.level => Err(HttpResponse::Forbidden().body("Cannot edit roles at or above your own level")),
+        Some(_) => Ok(()),
+    }
+}
+
+async fn fetch(pool: &DbPool, workspace_id: &str, name: &str) -> Result<Role, sqlx::Error> {
+    query_as::<_, Role>(&format!("{} AND r.name = $2", ROLE_SELECT))
+        .bind(workspace_id)
+        .bind(name)
+        .fetch_one(pool)
+        .await
+}
+
+// Responds with the role as it is now and records the change
+async fn respond(pool: &DbPool, req: &HttpRequest, grants: &Grants, action: &str, name: &str, before: Option<&Role>) -> HttpResponse {
+    match fetch(pool, &grants.workspace_id, name).await {
+        Ok(role) => {
+            audit::log(pool, &Actor::of(req), action, "role", name, before.and_then(audit::json), audit::json(&role)).await;
+            HttpResponse::Ok().json(role)
+        }
+        Err(e) => {
+            eprintln!("load role error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn set_permissions(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, role_id: &str, permissions: &[String]) -> Result<(), sqlx::Error> {
+    query("DELETE FROM role_permissions WHERE role_id = $1").bind(role_id).execute(&mut **tx).await?;
+    query("INSERT INTO role_permissions (role_id, permission) SELECT $1, UNNEST($2::TEXT[]) ON CONFLICT DO NOTHING")
+        .bind(role_id)
+        .bind(permissions)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// ─── GET /api/permissions ───
+
+pub async fn get_permissions() -> impl Responder {
+    HttpResponse::Ok().json(rbac::ALL)
+}
+
+// ─── GET /api/roles ───
+
+pub async fn get_roles(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let grants = match caller(&req) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    let roles = query_as::<_, Role>(&format!("{} ORDER BY r.level DESC, r.name", ROLE_SELECT))
+        .bind(&grants.workspace_id)
+        .fetch_all(pool.get_ref())
+        .await;
+    match roles {
+        Ok(roles) => HttpResponse::Ok().json(roles),
+        Err(e) => {
+            eprintln!("get_roles error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/roles ───
+
+pub async fn create_role(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<CreateRoleRequest>) -> impl Responder {
+    let grants = match caller(&req) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let name = body.name.trim().to_lowercase();
+    let valid_name = (2..=32).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid_name {
+        return HttpResponse::BadRequest().body("name must be 2 to 32 characters: a-z, 0-9, _ or -, starting with a letter");
+    }
+    if let Err(resp) = check_definition(&grants, Some(body.level), Some(&body.permissions)) {
+        return resp;
+    }
+    let db = pool.get_ref();
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        // Names are taken by the built-in roles and the workspace's own;
+        // other workspaces are free to use the same one
+        let created: Option<String> = query_scalar(
+            "INSERT INTO roles (id, name, description, level, workspace_id)
+             SELECT $1, $2, $3, $4, $5
+             WHERE NOT EXISTS (SELECT 1 FROM roles WHERE name = $2 AND (workspace_id IS NULL OR workspace_id = $5))
+             ON CONFLICT DO NOTHING
+             RETURNING id"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&name)
+        .bind(body.description.as_deref().unwrap_or_default().trim())
+        .bind(body.level)
+        .bind(&grants.workspace_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(role_id) = created else {
+            return Ok(false);
+        };
+        set_permissions(&mut tx, &role_id, &body.permissions).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => respond(db, &req, &grants, "role.create", &name, None).await,
+        Ok(false) => HttpResponse::Conflict().body("Role already exists"),
+        Err(e) => {
+            eprintln!("create_role error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── PUT /api/roles/{name} ───
+
+// Changes apply to the role's members on their next request
+pub async fn update_role(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<EditRoleRequest>) -> impl Responder {
+    let grants = match caller(&req) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let name = path.into_inner();
+    let db = pool.get_ref();
+
+    if let Err(resp) = editable(db, &grants, &name).await {
+        return resp;
+    }
+    if let Err(resp) = check_definition(&grants, body.level, body.permissions.as_deref()) {
+        return resp;
+    }
+    let before = fetch(db, &grants.workspace_id, &name).await.ok();
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        let role_id: String = query_scalar(
+            "UPDATE roles SET description = COALESCE($1, description), level = COALESCE($2, level)
+             WHERE workspace_id = $3 AND name = $4 RETURNING id"
+        )
+        .bind(body.description.as_deref().map(str::trim))
+        .bind(body.level)
+        .bind(&grants.workspace_id)
+        .bind(&name)
+        .fetch_one(&mut *tx)
+        .await?;
+        if let Some(ref permissions) = body.permissions {
+            set_permissions(&mut tx, &role_id, permissions).await?;
+        }
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(_) => respond(db, &req, &grants, "role.update", &name, before.as_ref()).await,
+        Err(e) => {
+            eprintln!("update_role error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── DELETE /api/roles/{name} ───
+
+pub async fn delete_role(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let grants = match caller(&req) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let name = path.into_inner();
+    let db = pool.get_ref();
+
+    if let Err(resp) = editable(db, &grants, &name).await {
+        return resp;
+    }
+    let in_use: i64 = query_scalar("SELECT COUNT(*) FROM workspace_members WHERE workspace_id = $1 AND role = $2")
+        .bind(&grants.workspace_id)
+        .bind(&name)
+        .fetch_one(db)
+        .await
+        .unwrap_or(0);
+    if in_use > 0 {
+        return HttpResponse::Conflict().body(format!("Role is assigned to {} users", in_use));
+    }
+
+    let before = fetch(db, &grants.workspace_id, &name).await.ok();
+    let deleted = query("DELETE FROM roles WHERE workspace_id = $1 AND name = $2 AND NOT builtin")
+        .bind(&grants.workspace_id)
+        .bind(&name)
+        .execute(db)
+        .await;
+    match deleted {
+        Ok(_) => {
+            audit::log(db, &Actor::of(&req), "role.delete", "role", &name, before.as_ref().and_then(audit::json), None).await;
+            HttpResponse::Ok().finish()
+        }
+        Err(e) => {
+            eprintln!("delete_role error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use sqlx::query_scalar;
+
+use crate::db::DbPool;
+use crate::models::user::AuthClaims;
+use crate::rbac;
+use crate::session;
+
+fn caller(req: &HttpRequest) -> Option<AuthClaims> {
+    req.extensions().get::<AuthClaims>().cloned()
+}
+
+// Same reach as other account-wide changes: sessions span every workspace
+// of the user. The caller's own sessions go through /api/me/sessions.
+async fn check_target(pool: &DbPool, req: &HttpRequest, user_id: &str) -> Result<(), HttpResponse> {
+    let Some(grants) = rbac::grants(req) else {
+        return Err(HttpResponse::Unauthorized().finish());
+    };
+    rbac::account(pool, &grants, user_id, true).await.map(|_| ())
+}
+
+async fn list(pool: &DbPool, user_id: &str, current: Option<&str>) -> HttpResponse {
+    match session::list(pool, user_id, current).await {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => {
+            eprintln!("list sessions error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn revoke_one(pool: &DbPool, user_id: &str, session_id: &str) -> HttpResponse {
+    let owned: Option<String> = query_scalar("SELECT id FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL")
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    if owned.is_none() {
+        return HttpResponse::NotFound().finish();
+    }
+
+    match session::revoke(pool, session_id).await {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({"revoked": 1})),
+        Err(e) => {
+            eprintln!("revoke session error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn revoke_all(pool: &DbPool, user_id: &str, except: Option<&str>) -> HttpResponse {
+    match session::revoke_all(pool, user_id, except).await {
+        Ok(count) => HttpResponse::Ok().json(serde_json::json!({"revoked": count})),
+        Err(e) => {
+            eprintln!("revoke sessions error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── GET /api/me/sessions ───
+
+pub async fn get_my_sessions(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    list(pool.get_ref(), &claims.sub, Some(&claims.sid)).await
+}
+
+// ─── DELETE /api/me/sessions/{id} ───
+
+// Revoking the current session is the same as logging out
+pub async fn revoke_my_session(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    revoke_one(pool.get_ref(), &claims.sub, &path.into_inner()).await
+}
+
+// ─── DELETE /api/me/sessions ───
+
+// Signs out everywhere but here
+pub async fn revoke_other_sessions(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    revoke_all(pool.get_ref(), &claims.sub, Some(&claims.sid)).await
+}
+
+// ─── GET /api/users/{id}/sessions ───
+
+pub async fn get_user_sessions(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+    if let Err(resp) = check_target(pool.get_ref(), &req, &user_id).await {
+        return resp;
+    }
+    list(pool.get_ref(), &user_id, Some(&claims.sid)).await
+}
+
+// ─── DELETE /api/users/{id}/sessions/{session_id} ───
+
+pub async fn revoke_user_session(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
+    let (user_id, session_id) = path.into_inner();
+    if let Err(resp) = check_target(pool.get_ref(), &req, &user_id).await {
+        return resp;
+    }
+    revoke_one(pool.get_ref(), &user_id, &session_id).await
+}
+
+// ─── DELETE /api/users/{id}/sessions ───
+
+pub async fn revoke_user_sessions(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+    if let Err(resp) = check_target(pool.get_ref(), &req, &user_id).await {
+        return resp;
+    }
+    // Keep the caller signed in when they target themselves
+    revoke_all(pool.get_ref(), &user_id, Some(&claims.sid)).await
+}
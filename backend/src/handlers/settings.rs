@@ -0,0 +1,201 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::{Duration, NaiveDate};
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::DbPool;
+use crate::lock;
+use crate::models::settings::*;
+use crate::models::user::AuthClaims;
+use crate::pagination;
+use crate::rbac;
+use crate::rounding;
+use crate::tz;
+
+// Settings of the caller's active workspace
+fn workspace(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<AuthClaims>().map(|c| c.wid.clone())
+}
+
+async fn load(pool: &DbPool, workspace_id: &str) -> Result<WorkspaceSettings, sqlx::Error> {
+    query_as::<_, WorkspaceSettings>(
+        "SELECT currency, default_hourly_rate_cents, rounding_mode, rounding_step_minutes, rounding_min_seconds,
+                lock_date, lock_timezone, locked_until, require_2fa_for_admins, updated_at
+         FROM workspace_settings WHERE workspace_id = $1"
+    )
+    .bind(workspace_id)
+    .fetch_one(pool)
+    .await
+}
+
+// ─── GET /api/settings ───
+
+// Readable by everyone so clients can format amounts
+pub async fn get_settings(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(workspace_id) = workspace(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    match load(pool.get_ref(), &workspace_id).await {
+        Ok(s) => HttpResponse::Ok().json(s),
+        Err(e) => {
+            eprintln!("get_settings error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── PUT /api/settings ───
+
+pub async fn update_settings(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<UpdateSettingsRequest>,
+) -> impl Responder {
+    let Some(workspace_id) = workspace(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    // Rates are stored without currency, so changing it does not convert them
+    let currency = body.currency.as_deref().map(|c| c.trim().to_uppercase());
+    if let Some(ref c) = currency {
+        if c.len() != 3 || !c.chars().all(|ch| ch.is_ascii_uppercase()) {
+            return HttpResponse::BadRequest().body("currency must be a 3-letter ISO 4217 code");
+        }
+    }
+    if let Some(Some(rate)) = body.default_hourly_rate_cents {
+        if rate < 0 {
+            return HttpResponse::BadRequest().body("default_hourly_rate_cents must not be negative");
+        }
+    }
+
+    if let Err(msg) = rounding::validate(body.rounding_mode.as_deref(), body.rounding_step_minutes, body.rounding_min_seconds) {
+        return HttpResponse::BadRequest().body(msg);
+    }
+
+    let result = query(
+        "UPDATE workspace_settings SET
+             currency = COALESCE($1, currency),
+             default_hourly_rate_cents = CASE WHEN $2 THEN $3 ELSE default_hourly_rate_cents END,
+             rounding_mode = COALESCE($4, rounding_mode),
+             rounding_step_minutes = COALESCE($5, rounding_step_minutes),
+             rounding_min_seconds = COALESCE($6, rounding_min_seconds),
+             require_2fa_for_admins = COALESCE($7, require_2fa_for_admins),
+             updated_at = NOW()
+         WHERE workspace_id = $8"
+    )
+    .bind(currency)
+    .bind(body.default_hourly_rate_cents.is_some())
+    .bind(body.default_hourly_rate_cents.flatten())
+    .bind(&body.rounding_mode)
+    .bind(body.rounding_step_minutes)
+    .bind(body.rounding_min_seconds)
+    .bind(body.require_2fa_for_admins)
+    .bind(&workspace_id)
+    .execute(pool.get_ref())
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("update_settings error: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    match load(pool.get_ref(), &workspace_id).await {
+        Ok(s) => HttpResponse::Ok().json(s),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── PUT /api/settings/lock ───
+
+// Closes every day up to and including `lock_date`. Moving the date back
+// or clearing it reopens a closed period and takes period.reopen.
+pub async fn update_lock(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<UpdateLockRequest>,
+) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let can_reopen = rbac::grants(&req).is_some_and(|g| g.has(rbac::PERIOD_REOPEN));
+    let db = pool.get_ref();
+
+    let Some(lock_date) = body.lock_date else {
+        return HttpResponse::BadRequest().body("lock_date is required (null to clear it)");
+    };
+    let zone = match tz::resolve(db, &user_id, body.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    if lock_date.is_some_and(|d| d > tz::today(zone)) {
+        return HttpResponse::BadRequest().body("lock_date cannot be in the future");
+    }
+
+    let current: Option<NaiveDate> = query_scalar("SELECT lock_date FROM workspace_settings WHERE workspace_id = $1")
+        .bind(&workspace_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None)
+        .flatten();
+    let reopens = match (current, lock_date) {
+        (Some(_), None) => true,
+        (Some(cur), Some(new)) => new < cur,
+        (None, _) => false,
+    };
+    if reopens && !can_reopen {
+        return rbac::forbidden(rbac::PERIOD_REOPEN);
+    }
+
+    let result = query(
+        "UPDATE workspace_settings SET lock_date = $1, lock_timezone = $2, locked_until = $3, updated_at = NOW()
+         WHERE workspace_id = $4"
+    )
+    .bind(lock_date)
+    .bind(zone.name())
+    .bind(lock_date.map(|d| tz::start_of_day(zone, d + Duration::days(1))))
+    .bind(&workspace_id)
+    .execute(db)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("update_lock error: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    let action = if lock_date.is_some() { "set" } else { "clear" };
+    lock::log(db, &workspace_id, &user_id, action, lock_date, None, body.reason.as_deref().unwrap_or_default().trim()).await;
+
+    match load(db, &workspace_id).await {
+        Ok(s) => HttpResponse::Ok().json(s),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── GET /api/settings/lock/log ───
+
+pub async fn get_lock_log(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(workspace_id) = workspace(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let rows = query_as::<_, PeriodLockEvent>(
+        "SELECT l.id, l.actor_id, u.username AS actor_username, l.action, l.lock_date, l.entry_id, l.reason, l.created_at
+         FROM period_lock_log l
+         LEFT JOIN users u ON u.id = l.actor_id
+         WHERE l.workspace_id = $2
+         ORDER BY l.id DESC
+         LIMIT $1"
+    )
+    .bind(pagination::MAX_LIMIT)
+    .bind(&workspace_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match rows {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_lock_log error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -39,14 +39,14 @@ pub async fn create_subtask(
     path: web::Path<String>,
     body: web::Json<CreateSubtaskRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let task_id = path.into_inner();
     let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    let now = Utc::now();
 
     // Ensure user owns the task
     // Technically subtask insertion with bad task_id/user_id combo would fail FK if we were strict,
@@ -56,8 +56,8 @@ pub async fn create_subtask(
     // No, we should verify ownership of task_id first strictly speaking.
     // But let's assume if we insert with user_id to subtasks, and we query subtasks by user_id, it is safe.
     // However, standard is check task ownership.
-    let task_exists = sqlx::query("SELECT id FROM tasks WHERE id = $1 AND user_id = $2")
-        .bind(&task_id).bind(&user_id).fetch_optional(pool.get_ref()).await.unwrap_or(None);
+    let task_exists = sqlx::query("SELECT id FROM tasks WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
+        .bind(&task_id).bind(&user_id).bind(&workspace_id).fetch_optional(pool.get_ref()).await.unwrap_or(None);
     
     if task_exists.is_none() {
         return HttpResponse::NotFound().finish(); // Or Forbidden
@@ -81,7 +81,7 @@ pub async fn create_subtask(
     .bind(&task_id)
     .bind(&body.title)
     .bind(position)
-    .bind(&now)
+    .bind(now)
     .bind(&user_id)
     .execute(pool.get_ref())
     .await;
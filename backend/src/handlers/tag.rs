@@ -0,0 +1,183 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::Utc;
+use uuid::Uuid;
+use sqlx::{query, query_as};
+
+use crate::db::DbPool;
+use crate::models::tag::*;
+use crate::models::user::AuthClaims;
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.is_unique_violation())
+}
+
+// ─── GET /api/tags ───
+
+pub async fn get_tags(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let result = query_as::<_, Tag>(
+        "SELECT tg.id, tg.name, tg.color, tg.created_at, tg.user_id,
+                COUNT(tt.task_id)::BIGINT AS task_count
+         FROM tags tg
+         LEFT JOIN task_tags tt ON tt.tag_id = tg.id
+         WHERE tg.user_id = $1
+         GROUP BY tg.id
+         ORDER BY tg.name ASC"
+    )
+    .bind(&user_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(tags) => HttpResponse::Ok().json(tags),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── POST /api/tags ───
+
+pub async fn create_tag(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<CreateTagRequest>,
+) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().body("Tag name is required");
+    }
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let color = body.color.clone().unwrap_or_else(|| "#64748b".into());
+
+    let result = query(
+        "INSERT INTO tags (id, name, color, created_at, user_id) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(&id)
+    .bind(&name)
+    .bind(&color)
+    .bind(now)
+    .bind(&user_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Created().json(Tag {
+            id,
+            name,
+            color,
+            created_at: now,
+            user_id,
+            task_count: 0,
+        }),
+        Err(e) if is_unique_violation(&e) => HttpResponse::Conflict().body("Tag already exists"),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── PUT /api/tags/{id} ───
+
+pub async fn update_tag(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<UpdateTagRequest>,
+) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let tag_id = path.into_inner();
+
+    if let Some(ref name) = body.name {
+        let result = query("UPDATE tags SET name = $1 WHERE id = $2 AND user_id = $3")
+            .bind(name.trim()).bind(&tag_id).bind(&user_id)
+            .execute(pool.get_ref()).await;
+        if let Err(e) = result {
+            if is_unique_violation(&e) {
+                return HttpResponse::Conflict().body("Tag already exists");
+            }
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+    if let Some(ref color) = body.color {
+        query("UPDATE tags SET color = $1 WHERE id = $2 AND user_id = $3")
+            .bind(color).bind(&tag_id).bind(&user_id)
+            .execute(pool.get_ref()).await.ok();
+    }
+
+    let tag = query_as::<_, Tag>(
+        "SELECT id, name, color, created_at, user_id,
+                (SELECT COUNT(*) FROM task_tags WHERE tag_id = tags.id)::BIGINT AS task_count
+         FROM tags WHERE id = $1 AND user_id = $2"
+    )
+    .bind(&tag_id)
+    .bind(&user_id)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match tag {
+        Ok(t) => HttpResponse::Ok().json(t),
+        Err(_) => HttpResponse::NotFound().finish(),
+    }
+}
+
+// ─── DELETE /api/tags/{id} ───
+
+pub async fn delete_tag(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let tag_id = path.into_inner();
+
+    // task_tags rows go with it (ON DELETE CASCADE)
+    let result = query("DELETE FROM tags WHERE id = $1 AND user_id = $2")
+        .bind(&tag_id)
+        .bind(&user_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(serde_json::json!({"deleted": true})),
+        Ok(_) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// Replaces the tag set of a task. Only the user's own tags are attached;
+// unknown ids are ignored.
+pub async fn set_task_tags(pool: &DbPool, task_id: &str, user_id: &str, tag_ids: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    query("DELETE FROM task_tags WHERE task_id = $1")
+        .bind(task_id)
+        .execute(&mut *tx)
+        .await?;
+    query(
+        "INSERT INTO task_tags (task_id, tag_id)
+         SELECT $1, id FROM tags WHERE id = ANY($2) AND user_id = $3
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(task_id)
+    .bind(tag_ids)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
@@ -1,11 +1,19 @@
+use std::collections::BTreeMap;
+
 use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
 use uuid::Uuid;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, Postgres, QueryBuilder};
 
+use crate::audit::{self, Actor};
 use crate::db::DbPool;
 use crate::models::task::*;
 use crate::models::user::AuthClaims;
+use crate::handlers::{project, tag::set_task_tags};
+use crate::lock;
+use crate::models::settings::LockOverrideQuery;
+use crate::pagination::{self, csv, Cursor};
+use crate::rbac;
 
 // ─── Shared SQL fragments ───
 
@@ -16,10 +24,15 @@ const TASK_SELECT: &str =
             p.name AS project_name,
             p.color AS project_color,
             t.user_id,
-            COALESCE(SUM(e.duration_minutes), 0)::BIGINT AS total_minutes,
+            t.assignee_id,
+            (SELECT username FROM users WHERE id = t.assignee_id) AS assignee_username,
+            ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT AS total_minutes,
             COUNT(e.id)::BIGINT AS entry_count,
             COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id), 0)::BIGINT AS subtask_count,
-            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done
+            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done,
+            COALESCE((SELECT json_agg(json_build_object('id', tg.id, 'name', tg.name, 'color', tg.color) ORDER BY tg.name)
+                      FROM task_tags tt JOIN tags tg ON tg.id = tt.tag_id
+                      WHERE tt.task_id = t.id), '[]'::json) AS tags
      FROM tasks t
      LEFT JOIN time_entries e ON e.task_id = t.id
      LEFT JOIN projects p ON p.id = t.project_id";
@@ -31,32 +44,219 @@ const TASK_SELECT_SINGLE: &str =
             p.name AS project_name,
             p.color AS project_color,
             t.user_id,
-            COALESCE((SELECT SUM(duration_minutes) FROM time_entries WHERE task_id = t.id), 0)::BIGINT AS total_minutes,
+            t.assignee_id,
+            (SELECT username FROM users WHERE id = t.assignee_id) AS assignee_username,
+            ROUND(COALESCE((SELECT SUM(duration_seconds) FROM time_entries WHERE task_id = t.id), 0) / 60.0)::BIGINT AS total_minutes,
             COALESCE((SELECT COUNT(*) FROM time_entries WHERE task_id = t.id), 0)::BIGINT AS entry_count,
             COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id), 0)::BIGINT AS subtask_count,
-            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done
+            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done,
+            COALESCE((SELECT json_agg(json_build_object('id', tg.id, 'name', tg.name, 'color', tg.color) ORDER BY tg.name)
+                      FROM task_tags tt JOIN tags tg ON tg.id = tt.tag_id
+                      WHERE tt.task_id = t.id), '[]'::json) AS tags
      FROM tasks t
      LEFT JOIN projects p ON p.id = t.project_id
      WHERE t.id = $1";
 
+const ADMIN_TASK_SELECT: &str =
+    "SELECT t.id, t.title, t.description, t.category, t.status, t.priority,
+            t.start_date, t.due_date, t.created_at, t.updated_at,
+            t.project_id,
+            p.name AS project_name,
+            p.color AS project_color,
+            t.user_id,
+            u.username,
+            u.full_name,
+            t.assignee_id,
+            (SELECT username FROM users WHERE id = t.assignee_id) AS assignee_username,
+            ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT AS total_minutes,
+            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id), 0)::BIGINT AS subtask_count,
+            COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done,
+            COALESCE((SELECT json_agg(json_build_object('id', tg.id, 'name', tg.name, 'color', tg.color) ORDER BY tg.name)
+                      FROM task_tags tt JOIN tags tg ON tg.id = tt.tag_id
+                      WHERE tt.task_id = t.id), '[]'::json) AS tags
+     FROM tasks t
+     LEFT JOIN time_entries e ON e.task_id = t.id
+     LEFT JOIN projects p ON p.id = t.project_id
+     JOIN users u ON u.id = t.user_id";
+
+// ─── Listing: filters, sort keys and keyset pagination ───
+
+struct TaskSort {
+    key: &'static str,
+    expr: &'static str,     // ORDER BY expression; never NULL so row comparison works
+    sql_type: &'static str, // cast applied to the cursor value
+}
+
+const TASK_SORTS: &[TaskSort] = &[
+    TaskSort { key: "updated_at", expr: "t.updated_at", sql_type: "timestamptz" },
+    TaskSort { key: "created_at", expr: "t.created_at", sql_type: "timestamptz" },
+    TaskSort { key: "due_date", expr: "COALESCE(t.due_date, 'infinity'::date)", sql_type: "date" },
+    TaskSort { key: "title", expr: "t.title", sql_type: "text" },
+];
+
+struct TaskPage {
+    sort: &'static TaskSort,
+    desc: bool,
+    cursor: Option<Cursor>,
+    limit: Option<i64>,
+}
+
+impl TaskPage {
+    fn from_query(q: &TaskListQuery) -> Result<Self, HttpResponse> {
+        let key = q.sort.as_deref().unwrap_or("updated_at");
+        let sort = match TASK_SORTS.iter().find(|s| s.key == key) {
+            Some(s) => s,
+            None => return Err(HttpResponse::BadRequest().body(format!("Unknown sort key: {}", key))),
+        };
+        let desc = match q.order.as_deref().unwrap_or("desc") {
+            "desc" => true,
+            "asc" => false,
+            other => return Err(HttpResponse::BadRequest().body(format!("Unknown order: {}", other))),
+        };
+        let limit = pagination::parse_limit(q.limit)?;
+
+        let mut page = TaskPage { sort, desc, cursor: None, limit };
+        if let Some(token) = q.cursor.as_deref().filter(|t| !t.is_empty()) {
+            // A cursor is only valid for the ordering it was issued under
+            match Cursor::decode(token) {
+                Some(c) if c.order == page.order_key() => page.cursor = Some(c),
+                _ => return Err(HttpResponse::BadRequest().body("Invalid cursor")),
+            }
+        }
+        Ok(page)
+    }
+
+    fn order_key(&self) -> String {
+        format!("{}:{}", self.sort.key, if self.desc { "desc" } else { "asc" })
+    }
+
+    fn cursor(&self, updated_at: DateTime<Utc>, created_at: DateTime<Utc>, due_date: Option<NaiveDate>, title: &str, id: &str) -> Cursor {
+        let value = match self.sort.key {
+            "created_at" => created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+            "due_date" => due_date.map(|d| d.to_string()).unwrap_or_else(|| "infinity".into()),
+            "title" => title.to_string(),
+            _ => updated_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        };
+        Cursor { order: self.order_key(), value, id: id.to_string() }
+    }
+
+    // Appends the keyset condition, then GROUP BY / ORDER BY / LIMIT
+    fn push(&self, qb: &mut QueryBuilder<'_, Postgres>, group_by: &str) {
+        let dir = if self.desc { "DESC" } else { "ASC" };
+        if let Some(ref c) = self.cursor {
+            qb.push(format!(" AND ({}, t.id) {} (", self.sort.expr, if self.desc { "<" } else { ">" }));
+            qb.push_bind(c.value.clone());
+            qb.push(format!("::{}, ", self.sort.sql_type));
+            qb.push_bind(c.id.clone());
+            qb.push(")");
+        }
+        qb.push(format!(" {} ORDER BY {} {}, t.id {}", group_by, self.sort.expr, dir, dir));
+        if let Some(limit) = self.limit {
+            qb.push(" LIMIT ").push_bind(limit + 1);
+        }
+    }
+}
+
+fn push_task_filters(qb: &mut QueryBuilder<'_, Postgres>, q: &TaskListQuery) {
+    if let Some(v) = csv(&q.status)     { qb.push(" AND t.status = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&q.priority)   { qb.push(" AND t.priority = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&q.project_id) { qb.push(" AND t.project_id = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&q.category)   { qb.push(" AND t.category = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&q.tag_id) {
+        qb.push(" AND EXISTS (SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag_id = ANY(").push_bind(v).push("))");
+    }
+    if let Some(d) = q.due_from { qb.push(" AND t.due_date >= ").push_bind(d); }
+    if let Some(d) = q.due_to   { qb.push(" AND t.due_date <= ").push_bind(d); }
+    if let Some(text) = q.q.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let pattern = format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        qb.push(" AND (t.title ILIKE ").push_bind(pattern.clone())
+          .push(" OR t.description ILIKE ").push_bind(pattern).push(")");
+    }
+}
+
+// Task start/due dates arrive as "YYYY-MM-DD" (older clients send a full
+// RFC3339 timestamp). An empty string means "no date".
+fn parse_task_date(val: &str) -> Result<Option<NaiveDate>, HttpResponse> {
+    let val = val.trim();
+    if val.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(val, "%Y-%m-%d")
+        .or_else(|_| DateTime::parse_from_rfc3339(val).map(|d| d.date_naive()))
+        .map(Some)
+        .map_err(|_| HttpResponse::BadRequest().body(format!("Invalid date: {}", val)))
+}
+
+// Tasks can only go into projects of the workspace the caller owns or
+// edits
+async fn project_usable(pool: &DbPool, project_id: &str, user_id: &str, workspace_id: &str) -> bool {
+    project::member_role(pool, project_id, user_id, workspace_id).await.is_some_and(|r| r != "viewer")
+}
+
+// Whether the user can log time on a task: their own, one assigned to
+// them, or one in a project they own or edit
+pub async fn trackable(pool: &DbPool, task_id: &str, user_id: &str, workspace_id: &str) -> bool {
+    sqlx::query_scalar(
+        "SELECT EXISTS (
+             SELECT 1 FROM tasks t
+             WHERE t.id = $1 AND t.workspace_id = $3
+               AND (t.user_id = $2 OR t.assignee_id = $2
+                    OR EXISTS (SELECT 1 FROM project_members pm
+                               WHERE pm.project_id = t.project_id AND pm.user_id = $2 AND pm.role <> 'viewer'))
+         )"
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(workspace_id)
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false)
+}
+
+// Tasks go to their creator or a member of their project
+async fn assignable(pool: &DbPool, project_id: Option<&str>, creator_id: &str, assignee_id: &str, workspace_id: &str) -> bool {
+    if assignee_id == creator_id {
+        return true;
+    }
+    match project_id {
+        Some(pid) => project::member_role(pool, pid, assignee_id, workspace_id).await.is_some(),
+        None => false,
+    }
+}
+
 // ─── GET /api/tasks ───
 
-pub async fn get_tasks(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn get_tasks(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<TaskListQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
+    let page = match TaskPage::from_query(&query) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
 
-    // TASK_SELECT doesn't have WHERE, so we add it
-    let sql = format!("{} WHERE t.user_id = $1 GROUP BY t.id, p.name, p.color ORDER BY t.updated_at DESC", TASK_SELECT);
-    let result = query_as::<_, Task>(&sql)
-        .bind(&user_id)
+    // TASK_SELECT doesn't have WHERE, so we add it. Own and assigned
+    // tasks, and every task of the projects the user is a member of.
+    let mut qb = QueryBuilder::<Postgres>::new(TASK_SELECT);
+    qb.push(" WHERE t.workspace_id = ").push_bind(workspace_id);
+    qb.push(" AND (t.user_id = ").push_bind(user_id.clone())
+      .push(" OR t.assignee_id = ").push_bind(user_id.clone())
+      .push(" OR t.project_id IN (SELECT project_id FROM project_members WHERE user_id = ").push_bind(user_id.clone()).push("))");
+    if query.assigned_to_me == Some(true) { qb.push(" AND t.assignee_id = ").push_bind(user_id.clone()); }
+    if query.created_by_me == Some(true)  { qb.push(" AND t.user_id = ").push_bind(user_id); }
+    push_task_filters(&mut qb, &query);
+    page.push(&mut qb, "GROUP BY t.id, p.name, p.color");
+
+    let result = qb.build_query_as::<Task>()
         .fetch_all(pool.get_ref())
         .await;
 
     match result {
-        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Ok(tasks) => pagination::page_response(tasks, page.limit, |t| {
+            page.cursor(t.updated_at, t.created_at, t.due_date, &t.title, &t.id)
+        }),
         Err(e) => {
             eprintln!("get_tasks error: {:?}", e);
             HttpResponse::InternalServerError().finish()
@@ -71,14 +271,14 @@ pub async fn create_task(
     req: HttpRequest,
     body: web::Json<CreateTaskRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
 
     let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    let now = Utc::now();
     let desc = body.description.clone().unwrap_or_default();
     let cat = body.category.clone().unwrap_or_else(|| "General".into());
     let priority = body.priority.clone().unwrap_or_else(|| "normal".into());
@@ -91,9 +291,31 @@ pub async fn create_task(
         Some(project_id_input)
     };
 
+    if let Some(ref pid) = project_id {
+        if !project_usable(pool.get_ref(), pid, &user_id, &workspace_id).await {
+            return HttpResponse::BadRequest().body("Project not found");
+        }
+    }
+
+    let assignee_id = body.assignee_id.clone().filter(|a| !a.is_empty());
+    if let Some(ref aid) = assignee_id {
+        if !assignable(pool.get_ref(), project_id.as_deref(), &user_id, aid, &workspace_id).await {
+            return HttpResponse::BadRequest().body("Tasks can only be assigned to members of their project");
+        }
+    }
+
+    let due_date = match parse_task_date(body.due_date.as_deref().unwrap_or_default()) {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+    let start_date = match parse_task_date(body.start_date.as_deref().unwrap_or_default()) {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+
     let result = query(
-        "INSERT INTO tasks (id, title, description, category, status, priority, project_id, due_date, start_date, created_at, updated_at, user_id)
-         VALUES ($1, $2, $3, $4, 'pending', $5, $6, $7, $8, $9, $10, $11)"
+        "INSERT INTO tasks (id, title, description, category, status, priority, project_id, due_date, start_date, created_at, updated_at, user_id, workspace_id, assignee_id)
+         VALUES ($1, $2, $3, $4, 'pending', $5, $6, $7, $8, $9, $10, $11, $12, $13)"
     )
     .bind(&id)
     .bind(&body.title)
@@ -101,14 +323,25 @@ pub async fn create_task(
     .bind(&cat)
     .bind(&priority)
     .bind(&project_id)
-    .bind(&body.due_date)
-    .bind(&body.start_date)
-    .bind(&now)
-    .bind(&now)
+    .bind(due_date)
+    .bind(start_date)
+    .bind(now)
+    .bind(now)
     .bind(&user_id)
+    .bind(&workspace_id)
+    .bind(&assignee_id)
     .execute(pool.get_ref())
     .await;
 
+    if result.is_ok() {
+        if let Some(ref tag_ids) = body.tag_ids {
+            if let Err(e) = set_task_tags(pool.get_ref(), &id, &user_id, tag_ids).await {
+                eprintln!("create_task tags error: {:?}", e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        }
+    }
+
     match result {
         Ok(_) => {
             // Fetch the full task with project info
@@ -131,15 +364,15 @@ pub async fn create_task(
 
 // ─── PUT /api/tasks/{id} ───
 
-async fn update_field(pool: &DbPool, col: &str, val: &str, now: &str, task_id: &str, user_id: &str) -> bool {
-    let sql = format!("UPDATE tasks SET {} = $1, updated_at = $2 WHERE id = $3 AND user_id = $4", col);
-    query(&sql).bind(val).bind(now).bind(task_id).bind(user_id).execute(pool).await.is_ok()
+// Tasks are changed in the workspace they belong to only
+async fn update_field(pool: &DbPool, col: &str, val: &str, now: DateTime<Utc>, task_id: &str, workspace_id: &str) -> bool {
+    let sql = format!("UPDATE tasks SET {} = $1, updated_at = $2 WHERE id = $3 AND workspace_id = $4", col);
+    query(&sql).bind(val).bind(now).bind(task_id).bind(workspace_id).execute(pool).await.is_ok()
 }
 
-async fn update_nullable_field(pool: &DbPool, col: &str, val: &str, now: &str, task_id: &str, user_id: &str) -> bool {
-    let bind_val: Option<&str> = if val.is_empty() { None } else { Some(val) };
-    let sql = format!("UPDATE tasks SET {} = $1, updated_at = $2 WHERE id = $3 AND user_id = $4", col);
-    query(&sql).bind(bind_val).bind(now).bind(task_id).bind(user_id).execute(pool).await.is_ok()
+async fn update_date_field(pool: &DbPool, col: &str, val: Option<NaiveDate>, now: DateTime<Utc>, task_id: &str, workspace_id: &str) -> bool {
+    let sql = format!("UPDATE tasks SET {} = $1, updated_at = $2 WHERE id = $3 AND workspace_id = $4", col);
+    query(&sql).bind(val).bind(now).bind(task_id).bind(workspace_id).execute(pool).await.is_ok()
 }
 
 pub async fn update_task(
@@ -148,23 +381,110 @@ pub async fn update_task(
     path: web::Path<String>,
     body: web::Json<UpdateTaskRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let task_id = path.into_inner();
-    let now = Utc::now().to_rfc3339();
+    let now = Utc::now();
     let db = pool.get_ref();
 
-    if let Some(ref v) = body.title       { if !update_field(db, "title", v, &now, &task_id, &user_id).await       { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.description  { if !update_field(db, "description", v, &now, &task_id, &user_id).await { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.category     { if !update_field(db, "category", v, &now, &task_id, &user_id).await    { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.status       { if !update_field(db, "status", v, &now, &task_id, &user_id).await      { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.priority     { if !update_field(db, "priority", v, &now, &task_id, &user_id).await    { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.project_id   { if !update_field(db, "project_id", v, &now, &task_id, &user_id).await  { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.due_date     { if !update_nullable_field(db, "due_date", v, &now, &task_id, &user_id).await   { return HttpResponse::InternalServerError().finish(); } }
-    if let Some(ref v) = body.start_date   { if !update_nullable_field(db, "start_date", v, &now, &task_id, &user_id).await { return HttpResponse::InternalServerError().finish(); } }
+    let due_date = match body.due_date.as_deref().map(parse_task_date).transpose() {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+    let start_date = match body.start_date.as_deref().map(parse_task_date).transpose() {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+
+    let task: Option<(String, Option<String>)> = query_as("SELECT user_id, project_id FROM tasks WHERE id = $1 AND workspace_id = $2")
+        .bind(&task_id)
+        .bind(&workspace_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    let Some((creator_id, current_project)) = task else {
+        return HttpResponse::NotFound().finish();
+    };
+    let is_creator = creator_id == user_id;
+
+    // Whoever can track time on the task (assignees, project owners and
+    // editors) can edit it; moving it and its tags, which are the
+    // creator's own, stay with the creator
+    if !is_creator {
+        if body.project_id.is_some() || body.tag_ids.is_some() {
+            return HttpResponse::Forbidden().body("Only the task's creator can move or tag it");
+        }
+        let edits = body.title.is_some() || body.description.is_some() || body.category.is_some()
+            || body.status.is_some() || body.priority.is_some() || due_date.is_some() || start_date.is_some();
+        if edits && !trackable(db, &task_id, &user_id, &workspace_id).await {
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    if let Some(v) = body.project_id.as_deref().filter(|v| !v.is_empty()) {
+        if !project_usable(db, v, &user_id, &workspace_id).await {
+            return HttpResponse::BadRequest().body("Project not found");
+        }
+    }
+
+    // Assigning is up to the task's creator, or anyone who may assign tasks
+    // in the workspace
+    if let Some(ref assignee) = body.assignee_id {
+        if !is_creator && !rbac::grants(&req).is_some_and(|g| g.has(rbac::TASKS_ASSIGN)) {
+            return HttpResponse::Forbidden().body("Only the task's creator can assign it");
+        }
+        if let Some(aid) = assignee.as_deref().filter(|a| !a.is_empty()) {
+            // Against the project the task is moving to, if any
+            let project_id = match body.project_id.as_deref() {
+                Some(v) => Some(v).filter(|v| !v.is_empty()),
+                None => current_project.as_deref(),
+            };
+            if !assignable(db, project_id, &creator_id, aid, &workspace_id).await {
+                return HttpResponse::BadRequest().body("Tasks can only be assigned to members of their project");
+            }
+        }
+    }
+
+    if let Some(ref v) = body.title       { if !update_field(db, "title", v, now, &task_id, &workspace_id).await       { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(ref v) = body.description  { if !update_field(db, "description", v, now, &task_id, &workspace_id).await { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(ref v) = body.category     { if !update_field(db, "category", v, now, &task_id, &workspace_id).await    { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(ref v) = body.status       { if !update_field(db, "status", v, now, &task_id, &workspace_id).await      { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(ref v) = body.priority     { if !update_field(db, "priority", v, now, &task_id, &workspace_id).await    { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(ref v) = body.project_id   { if !update_field(db, "project_id", v, now, &task_id, &workspace_id).await  { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(v) = due_date              { if !update_date_field(db, "due_date", v, now, &task_id, &workspace_id).await   { return HttpResponse::InternalServerError().finish(); } }
+    if let Some(v) = start_date            { if !update_date_field(db, "start_date", v, now, &task_id, &workspace_id).await { return HttpResponse::InternalServerError().finish(); } }
+
+    if let Some(ref assignee) = body.assignee_id {
+        let assignee = assignee.as_deref().filter(|a| !a.is_empty());
+        let assigned = query("UPDATE tasks SET assignee_id = $1, updated_at = $2 WHERE id = $3 AND workspace_id = $4")
+            .bind(assignee).bind(now).bind(&task_id).bind(&workspace_id).execute(db).await;
+        if assigned.is_err() {
+            return HttpResponse::InternalServerError().finish();
+        }
+    } else if body.project_id.is_some() {
+        // Moving the task away from its assignee's project unassigns it
+        let cleared = query(
+            "UPDATE tasks t SET assignee_id = NULL
+             WHERE t.id = $1 AND t.workspace_id = $2 AND t.assignee_id <> t.user_id
+               AND NOT EXISTS (SELECT 1 FROM project_members pm WHERE pm.project_id = t.project_id AND pm.user_id = t.assignee_id)"
+        )
+        .bind(&task_id)
+        .bind(&workspace_id)
+        .execute(db)
+        .await;
+        if cleared.is_err() {
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    if let Some(ref tag_ids) = body.tag_ids {
+        if set_task_tags(db, &task_id, &user_id, tag_ids).await.is_err() {
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
 
     let task = query_as::<_, Task>(TASK_SELECT_SINGLE)
         .bind(&task_id)
@@ -179,14 +499,52 @@ pub async fn update_task(
 
 // ─── DELETE /api/tasks/{id} ───
 
-pub async fn delete_task(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+// A task goes with all of its time entries, other members' included. None
+// may be on an invoice or in an approved week, nor in the closed period
+// without an override; the bypassed lock date is returned for the log.
+async fn entries_deletable(pool: &DbPool, task_id: &str, workspace_id: &str, override_reason: Option<&str>) -> Result<Option<NaiveDate>, HttpResponse> {
+    let entries: Vec<(Option<String>, DateTime<Utc>, Option<String>)> =
+        match query_as("SELECT user_id, start_time, invoice_id FROM time_entries WHERE task_id = $1")
+            .bind(task_id)
+            .fetch_all(pool)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                eprintln!("task entries error: {:?}", e);
+                return Err(HttpResponse::InternalServerError().finish());
+            }
+        };
+    if entries.iter().any(|(_, _, invoice_id)| invoice_id.is_some()) {
+        return Err(HttpResponse::Conflict().body("Task has time entries on an invoice"));
+    }
+
+    // Approved weeks are per user
+    let mut by_user: BTreeMap<String, Vec<DateTime<Utc>>> = BTreeMap::new();
+    for (user_id, start_time, _) in entries {
+        by_user.entry(user_id.unwrap_or_default()).or_default().push(start_time);
+    }
+    let mut overridden = None;
+    for (user_id, times) in &by_user {
+        if let Some(date) = lock::check(pool, workspace_id, user_id, times, override_reason).await? {
+            overridden = Some(date);
+        }
+    }
+    Ok(overridden)
+}
+
+pub async fn delete_task(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let task_id = path.into_inner();
     let db = pool.get_ref();
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
 
     // Ensure ownership before delete
     // We can just add user_id AND to the DELETE. 
@@ -199,17 +557,27 @@ pub async fn delete_task(pool: web::Data<DbPool>, req: HttpRequest, path: web::P
     // Actually, if we delete task first, children go away.
     // Let's rely on checking ownership ONCE.
 
-    let owner_check = query("SELECT id FROM tasks WHERE id = $1 AND user_id = $2")
+    let owner_check = query("SELECT id FROM tasks WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
         .bind(&task_id)
         .bind(&user_id)
+        .bind(&workspace_id)
         .fetch_optional(db)
         .await;
 
     if let Ok(Some(_)) = owner_check {
+        let overridden = match entries_deletable(db, &task_id, &workspace_id, override_reason.as_deref()).await {
+            Ok(d) => d,
+            Err(resp) => return resp,
+        };
+        let before = audit::task(db, &task_id).await;
         query("DELETE FROM active_timers WHERE task_id = $1").bind(&task_id).execute(db).await.ok();
         query("DELETE FROM subtasks WHERE task_id = $1").bind(&task_id).execute(db).await.ok();
         query("DELETE FROM time_entries WHERE task_id = $1").bind(&task_id).execute(db).await.ok();
         query("DELETE FROM tasks WHERE id = $1").bind(&task_id).execute(db).await.ok();
+        audit::log(db, &Actor::of(&req), "task.delete", "task", &task_id, before, None).await;
+        if let (Some(date), Some(reason)) = (overridden, override_reason.as_deref()) {
+            lock::log(db, &workspace_id, &user_id, "delete_task", Some(date), None, reason).await;
+        }
         HttpResponse::Ok().json(serde_json::json!({"deleted": true}))
     } else {
          HttpResponse::NotFound().finish()
@@ -223,13 +591,35 @@ pub struct BulkDeleteRequest {
     pub ids: Vec<String>,
 }
 
-pub async fn delete_tasks_bulk(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<BulkDeleteRequest>) -> impl Responder {
-     let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+pub async fn delete_tasks_bulk(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<BulkDeleteRequest>, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
+     let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let db = pool.get_ref();
+    let actor = Actor::of(&req);
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+
+    // All or nothing: one task with locked or invoiced time refuses the batch
+    let owned: Vec<String> = sqlx::query_scalar("SELECT id FROM tasks WHERE id = ANY($1) AND user_id = $2 AND workspace_id = $3")
+        .bind(&body.ids)
+        .bind(&user_id)
+        .bind(&workspace_id)
+        .fetch_all(db)
+        .await
+        .unwrap_or_default();
+    let mut overridden = Vec::new();
+    for id in &owned {
+        match entries_deletable(db, id, &workspace_id, override_reason.as_deref()).await {
+            Ok(Some(date)) => overridden.push(date),
+            Ok(None) => {}
+            Err(resp) => return resp,
+        }
+    }
 
     let mut deleted_count = 0;
     for id in &body.ids {
@@ -243,83 +633,65 @@ pub async fn delete_tasks_bulk(pool: web::Data<DbPool>, req: HttpRequest, body:
         // The previous code verified checking children deletion manually.
         // I will simplify to just delete from tasks with user_id check.
         
-        let result = query("DELETE FROM tasks WHERE id = $1 AND user_id = $2")
+        let before = audit::task(db, id).await;
+        let result = query("DELETE FROM tasks WHERE id = $1 AND user_id = $2 AND workspace_id = $3")
             .bind(id)
             .bind(&user_id)
+            .bind(&workspace_id)
             .execute(db)
             .await;
             
         if let Ok(res) = result {
             if res.rows_affected() > 0 {
                 deleted_count += 1;
+                audit::log(db, &actor, "task.bulk_delete", "task", id, before, None).await;
             }
         }
     }
+    if let Some(reason) = override_reason.as_deref() {
+        for date in overridden {
+            lock::log(db, &workspace_id, &user_id, "delete_task", Some(date), None, reason).await;
+        }
+    }
     HttpResponse::Ok().json(serde_json::json!({"deleted_count": deleted_count}))
 }
 
-// ─── GET /api/admin/tasks (admin only) ───
+// ─── GET /api/admin/tasks ───
 
-pub async fn get_all_tasks_admin(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    // Role check — extract role before extensions temporary is dropped
-    let role = {
-        let ext = req.extensions();
-        match ext.get::<AuthClaims>() {
-            Some(c) => c.role.clone(),
-            None => return HttpResponse::Unauthorized().finish(),
-        }
+pub async fn get_all_tasks_admin(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<TaskListQuery>) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let page = match TaskPage::from_query(&query) {
+        Ok(p) => p,
+        Err(resp) => return resp,
     };
-    if role != "admin" && role != "superadmin" {
-        return HttpResponse::Forbidden().body("Admin only");
-    }
 
-    let sql = if role == "superadmin" {
-        // superadmin sees all tasks from all users
-        "SELECT t.id, t.title, t.description, t.category, t.status, t.priority,
-                      t.start_date, t.due_date, t.created_at, t.updated_at,
-                      t.project_id,
-                      p.name AS project_name,
-                      p.color AS project_color,
-                      t.user_id,
-                      u.username,
-                      u.full_name,
-                      COALESCE(SUM(e.duration_minutes), 0)::BIGINT AS total_minutes,
-                      COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id), 0)::BIGINT AS subtask_count,
-                      COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done
-               FROM tasks t
-               LEFT JOIN time_entries e ON e.task_id = t.id
-               LEFT JOIN projects p ON p.id = t.project_id
-               JOIN users u ON u.id = t.user_id
-               GROUP BY t.id, p.name, p.color, u.username, u.full_name
-               ORDER BY t.updated_at DESC"
-    } else {
-        // admin sees only tasks from 'user' role
-        "SELECT t.id, t.title, t.description, t.category, t.status, t.priority,
-                      t.start_date, t.due_date, t.created_at, t.updated_at,
-                      t.project_id,
-                      p.name AS project_name,
-                      p.color AS project_color,
-                      t.user_id,
-                      u.username,
-                      u.full_name,
-                      COALESCE(SUM(e.duration_minutes), 0)::BIGINT AS total_minutes,
-                      COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id), 0)::BIGINT AS subtask_count,
-                      COALESCE((SELECT COUNT(*) FROM subtasks WHERE task_id = t.id AND completed = TRUE), 0)::BIGINT AS subtask_done
-               FROM tasks t
-               LEFT JOIN time_entries e ON e.task_id = t.id
-               LEFT JOIN projects p ON p.id = t.project_id
-               JOIN users u ON u.id = t.user_id
-               WHERE u.role = 'user'
-               GROUP BY t.id, p.name, p.color, u.username, u.full_name
-               ORDER BY t.updated_at DESC"
+    // Tasks in the workspace of the members the caller can manage
+    let members = match rbac::reachable_users(pool.get_ref(), &grants).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("get_all_tasks_admin error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
     };
+    let mut qb = QueryBuilder::<Postgres>::new(ADMIN_TASK_SELECT);
+    qb.push(" WHERE t.workspace_id = ").push_bind(grants.workspace_id.clone());
+    qb.push(" AND t.user_id = ANY(").push_bind(members).push(")");
+    if let Some(ref uid) = query.user_id {
+        qb.push(" AND t.user_id = ").push_bind(uid.clone());
+    }
+    push_task_filters(&mut qb, &query);
+    page.push(&mut qb, "GROUP BY t.id, p.name, p.color, u.username, u.full_name");
 
-    let result = query_as::<_, crate::models::task::AdminTask>(sql)
+    let result = qb.build_query_as::<AdminTask>()
         .fetch_all(pool.get_ref())
         .await;
 
     match result {
-        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Ok(tasks) => pagination::page_response(tasks, page.limit, |t| {
+            page.cursor(t.updated_at, t.created_at, t.due_date, &t.title, &t.id)
+        }),
         Err(e) => {
             eprintln!("get_all_tasks_admin error: {:?}", e);
             HttpResponse::InternalServerError().finish()
@@ -0,0 +1,128 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use sqlx::{query_as, query_scalar, Postgres, QueryBuilder};
+
+use crate::db::DbPool;
+use crate::models::throttle::*;
+use crate::models::user::AuthClaims;
+use crate::pagination::{self, csv};
+use crate::rbac::{self, Grants};
+use crate::session;
+use crate::throttle;
+
+fn caller(req: &HttpRequest) -> Option<(AuthClaims, Grants)> {
+    Some((req.extensions().get::<AuthClaims>()?.clone(), rbac::grants(req)?))
+}
+
+async fn reachable_users(pool: &DbPool, grants: &Grants) -> Result<Vec<String>, HttpResponse> {
+    rbac::reachable_users(pool, grants).await.map_err(|e| {
+        eprintln!("reachable users error: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })
+}
+
+// ─── POST /api/users/{id}/unlock ───
+
+// Lifts a lockout and backoff of the account early
+pub async fn unlock_user(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some((claims, grants)) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+    let db = pool.get_ref();
+
+    if let Err(resp) = rbac::account(db, &grants, &user_id, true).await {
+        return resp;
+    }
+    let username: String = match query_scalar("SELECT username FROM users WHERE id = $1").bind(&user_id).fetch_one(db).await {
+        Ok(username) => username,
+        Err(e) => {
+            eprintln!("unlock_user error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match throttle::clear_account(db, &username).await {
+        Ok(unlocked) => {
+            if unlocked {
+                let ip = session::client_ip(&req);
+                throttle::log(db, "unlocked", Some(&username), Some(&user_id), ip.as_deref(), Some(&claims.sub)).await;
+            }
+            HttpResponse::Ok().json(serde_json::json!({"unlocked": unlocked}))
+        }
+        Err(e) => {
+            eprintln!("unlock_user error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── GET /api/admin/lockouts ───
+
+pub async fn get_lockouts(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some((_, grants)) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let members = match reachable_users(pool.get_ref(), &grants).await {
+        Ok(ids) => ids,
+        Err(resp) => return resp,
+    };
+
+    // Only accounts within reach in the workspace; unknown usernames and
+    // addresses always
+    let rows = query_as::<_, Lockout>(
+        "SELECT t.scope, t.key, u.id AS user_id, t.failures, t.last_failure_at, t.locked_until
+         FROM login_throttle t
+         LEFT JOIN users u ON t.scope = 'account' AND u.username = t.key
+         WHERE t.locked_until > NOW() AND (u.id IS NULL OR u.id = ANY($1))
+         ORDER BY t.locked_until DESC"
+    )
+    .bind(members)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match rows {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_lockouts error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── GET /api/admin/login-events ───
+
+// Newest first
+pub async fn get_login_events(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<LoginEventQuery>) -> impl Responder {
+    let Some((_, grants)) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let members = match reachable_users(pool.get_ref(), &grants).await {
+        Ok(ids) => ids,
+        Err(resp) => return resp,
+    };
+    let limit = match pagination::parse_limit(query.limit) {
+        Ok(l) => l.unwrap_or(100),
+        Err(resp) => return resp,
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(
+        "SELECT e.id, e.event, e.username, e.user_id, e.ip, e.actor_id, a.username AS actor_username, e.created_at
+         FROM login_events e
+         LEFT JOIN users u ON u.id = e.user_id
+         LEFT JOIN users a ON a.id = e.actor_id
+         WHERE (u.id IS NULL OR u.id = ANY("
+    );
+    qb.push_bind(members).push("))");
+    if let Some(v) = csv(&query.event)   { qb.push(" AND e.event = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.user_id) { qb.push(" AND e.user_id = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.ip)      { qb.push(" AND e.ip = ANY(").push_bind(v).push(")"); }
+    qb.push(" ORDER BY e.id DESC LIMIT ").push_bind(limit);
+
+    match qb.build_query_as::<LoginEvent>().fetch_all(pool.get_ref()).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_login_events error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -3,9 +3,71 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use sqlx::{query, query_as};
 
+use crate::billing;
 use crate::db::DbPool;
+use crate::handlers::task;
+use crate::lock;
+use crate::models::settings::LockOverrideQuery;
 use crate::models::timer::*;
 use crate::models::user::AuthClaims;
+use crate::rounding;
+
+// Entries produced by the timer take the billable flag of their project
+fn entry_insert_sql() -> String {
+    format!(
+        "INSERT INTO time_entries (id, task_id, start_time, end_time, duration_seconds, duration_minutes, notes, created_at, user_id, raw_seconds, billable)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10, {})",
+        billing::default_billable_sql("$2")
+    )
+}
+
+const TIMER_SELECT: &str =
+    "SELECT at.id, at.task_id, t.title AS task_title, at.start_time, at.notes, at.created_at,
+            at.paused_at, at.paused_seconds, t.workspace_id
+     FROM active_timers at
+     JOIN tasks t ON t.id = at.task_id
+     WHERE at.user_id = $1
+     LIMIT 1";
+
+// Shared tasks stay in progress while any member's timer on them runs; the
+// caller's own timer is already gone when this is checked
+const NO_OTHER_TIMER: &str = "NOT EXISTS (SELECT 1 FROM active_timers WHERE task_id = tasks.id)";
+
+async fn fetch_timer(pool: &DbPool, user_id: &str) -> Option<ActiveTimer> {
+    query_as::<_, ActiveTimer>(TIMER_SELECT)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+}
+
+// Turns a timer into an entry, rounded by the task's policy, and removes it.
+// Returns the entry id (None when the run was under the discard threshold)
+// and the recorded minutes. The timer stays when the entry can't be saved.
+async fn finish_timer(pool: &DbPool, timer: &ActiveTimer, user_id: &str, now: DateTime<Utc>) -> Result<(Option<String>, i64), sqlx::Error> {
+    // Paused time is not worked time; a timer stopped while paused ended
+    // when the pause began
+    let raw_seconds = timer.elapsed_at(now);
+    let policy = rounding::policy_for_task(pool, &timer.task_id).await;
+
+    let mut tx = pool.begin().await?;
+    let result = match policy.apply(raw_seconds) {
+        Some(duration_seconds) => {
+            let entry_id = Uuid::new_v4().to_string();
+            let duration_minutes = rounding::to_minutes(duration_seconds);
+            query(&entry_insert_sql())
+                .bind(&entry_id).bind(&timer.task_id).bind(timer.start_time).bind(timer.paused_at.unwrap_or(now))
+                .bind(duration_seconds).bind(duration_minutes).bind(&timer.notes).bind(now).bind(user_id).bind(raw_seconds)
+                .execute(&mut *tx).await?;
+            (Some(entry_id), duration_minutes)
+        }
+        None => (None, 0),
+    };
+
+    query("DELETE FROM active_timers WHERE id = $1").bind(&timer.id).execute(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(result)
+}
 
 pub async fn start_timer(
     pool: web::Data<DbPool>,
@@ -13,124 +75,129 @@ pub async fn start_timer(
     path: web::Path<String>,
     body: web::Json<StartTimerRequest>,
 ) -> impl Responder {
-    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
-        claims.sub.clone()
+    let (user_id, workspace_id) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.wid.clone())
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let task_id = path.into_inner();
     let now = Utc::now();
-    let now_str = now.to_rfc3339();
 
-    // 1. Check if there's already an active timer FOR THIS USER — stop it first
-    let existing: Option<(String, String, String, String)> = query_as(
-        "SELECT id, task_id, start_time, notes FROM active_timers WHERE user_id = $1 LIMIT 1"
-    )
-    .bind(&user_id)
-    .fetch_optional(pool.get_ref())
-    .await
-    .unwrap_or(None);
-
-    if let Some((timer_id, old_task_id, start_time_str, old_notes)) = existing {
-        // Calculate duration
-        let start: DateTime<Utc> = start_time_str.parse().unwrap_or(now);
-        let duration_secs = (now - start).num_seconds().max(0);
-        let duration_minutes = (duration_secs as f64 / 60.0).ceil() as i64;
+    // Own or assigned tasks, or tasks of projects the user owns or edits
+    if !task::trackable(pool.get_ref(), &task_id, &user_id, &workspace_id).await {
+        return HttpResponse::NotFound().body("Task not found");
+    }
+    let task_title: String = sqlx::query_scalar("SELECT title FROM tasks WHERE id = $1")
+        .bind(&task_id)
+        .fetch_one(pool.get_ref())
+        .await
+        .unwrap_or_default();
 
+    // 1. Check if there's already an active timer FOR THIS USER — stop it first
+    if let Some(old) = fetch_timer(pool.get_ref(), &user_id).await {
+        // A timer running since before a lock has to be stopped explicitly
+        if let Err(resp) = lock::check(pool.get_ref(), &old.workspace_id, &user_id, &[old.start_time], None).await {
+            return resp;
+        }
         // Create a time_entry from the stopped timer
-        let entry_id = Uuid::new_v4().to_string();
-        query(
-            "INSERT INTO time_entries (id, task_id, start_time, end_time, duration_minutes, notes, created_at, user_id) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)"
-        )
-        .bind(&entry_id).bind(&old_task_id).bind(&start_time_str).bind(&now_str).bind(duration_minutes).bind(&old_notes).bind(&now_str).bind(&user_id)
-        .execute(pool.get_ref()).await.ok();
-
-        // Delete the old timer
-        query("DELETE FROM active_timers WHERE id = $1").bind(&timer_id).execute(pool.get_ref()).await.ok();
-
-        // Update old task
-        // Ensure we only update if it belongs to user? active_timer was user's, so task should be too.
-        query("UPDATE tasks SET status = 'pending', updated_at = $1 WHERE id = $2 AND status = 'in_progress'")
-            .bind(&now_str).bind(&old_task_id).execute(pool.get_ref()).await.ok();
+        if let Err(e) = finish_timer(pool.get_ref(), &old, &user_id, now).await {
+            eprintln!("start_timer error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        let old_task_id = old.task_id;
+
+        // Update old task, unless someone else is still timing it
+        query(&format!("UPDATE tasks SET status = 'pending', updated_at = $1 WHERE id = $2 AND status = 'in_progress' AND {}", NO_OTHER_TIMER))
+            .bind(now).bind(&old_task_id).execute(pool.get_ref()).await.ok();
     }
 
     // 2. Create new active timer
     let id = Uuid::new_v4().to_string();
     let notes = body.notes.clone().unwrap_or_default();
 
-    query(
+    let inserted = query(
         "INSERT INTO active_timers (id, task_id, start_time, notes, created_at, user_id) VALUES ($1,$2,$3,$4,$5,$6)"
     )
-    .bind(&id).bind(&task_id).bind(&now_str).bind(&notes).bind(&now_str).bind(&user_id)
-    .execute(pool.get_ref()).await.unwrap();
+    .bind(&id).bind(&task_id).bind(now).bind(&notes).bind(now).bind(&user_id)
+    .execute(pool.get_ref()).await;
+    match inserted {
+        Ok(_) => {}
+        // Another request started one for this user in the meantime
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return HttpResponse::Conflict().body("A timer is already running");
+        }
+        Err(e) => {
+            eprintln!("start_timer error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
 
     // Set task to in_progress
-    query("UPDATE tasks SET status = 'in_progress', updated_at = $1 WHERE id = $2 AND user_id = $3")
-        .bind(&now_str).bind(&task_id).bind(&user_id).execute(pool.get_ref()).await.ok();
-
-    // Fetch task title
-    let task_title: String = sqlx::query_scalar("SELECT title FROM tasks WHERE id = $1")
-        .bind(&task_id)
-        .fetch_one(pool.get_ref())
-        .await
-        .unwrap_or_default();
+    query("UPDATE tasks SET status = 'in_progress', updated_at = $1 WHERE id = $2")
+        .bind(now).bind(&task_id).execute(pool.get_ref()).await.ok();
 
     let timer = ActiveTimer {
         id,
         task_id,
         task_title,
-        start_time: now_str.clone(),
+        start_time: now,
         notes,
         elapsed_seconds: 0,
-        created_at: now_str,
+        created_at: now,
+        paused_at: None,
+        paused_seconds: 0,
+        workspace_id,
     };
 
     HttpResponse::Ok().json(timer)
 }
 
-pub async fn stop_timer(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+pub async fn stop_timer(pool: web::Data<DbPool>, req: HttpRequest, lock_query: web::Query<LockOverrideQuery>) -> impl Responder {
     let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
         claims.sub.clone()
     } else {
         return HttpResponse::Unauthorized().finish();
     };
     let now = Utc::now();
-    let now_str = now.to_rfc3339();
-
-    let existing: Option<(String, String, String, String)> = query_as(
-        "SELECT id, task_id, start_time, notes FROM active_timers WHERE user_id = $1 LIMIT 1"
-    )
-    .bind(&user_id)
-    .fetch_optional(pool.get_ref())
-    .await
-    .unwrap_or(None);
+    let override_reason = match lock::override_reason(&req, &lock_query) {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
 
-    match existing {
-        Some((timer_id, task_id, start_time_str, notes)) => {
-            let start: DateTime<Utc> = start_time_str.parse().unwrap_or(now);
-            let duration_secs = (now - start).num_seconds().max(0);
-            let duration_minutes = (duration_secs as f64 / 60.0).ceil() as i64;
+    match fetch_timer(pool.get_ref(), &user_id).await {
+        Some(timer) => {
+            // The entry would start where the timer did, possibly in a
+            // period closed since
+            let overridden = match lock::check(pool.get_ref(), &timer.workspace_id, &user_id, &[timer.start_time], override_reason.as_deref()).await {
+                Ok(d) => d,
+                Err(resp) => return resp,
+            };
 
             // Create time entry
-            let entry_id = Uuid::new_v4().to_string();
-            query(
-                "INSERT INTO time_entries (id, task_id, start_time, end_time, duration_minutes, notes, created_at, user_id) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)"
-            )
-            .bind(&entry_id).bind(&task_id).bind(&start_time_str).bind(&now_str).bind(duration_minutes).bind(&notes).bind(&now_str).bind(&user_id)
-            .execute(pool.get_ref()).await.ok();
-
-            // Delete timer
-            query("DELETE FROM active_timers WHERE id = $1").bind(&timer_id).execute(pool.get_ref()).await.ok();
+            let (entry_id, duration_minutes) = match finish_timer(pool.get_ref(), &timer, &user_id, now).await {
+                Ok(finished) => finished,
+                Err(e) => {
+                    eprintln!("stop_timer error: {:?}", e);
+                    return HttpResponse::InternalServerError().finish();
+                }
+            };
+            if let (Some(date), Some(reason), Some(id)) = (overridden, override_reason.as_deref(), entry_id.as_deref()) {
+                lock::log(pool.get_ref(), &timer.workspace_id, &user_id, "stop_timer", Some(date), Some(id), reason).await;
+            }
+            let task_id = timer.task_id;
 
-            // Update task status back to pending
-            query("UPDATE tasks SET status = 'pending', updated_at = $1 WHERE id = $2")
-                .bind(&now_str).bind(&task_id).execute(pool.get_ref()).await.ok();
+            // Update task status back to pending, unless someone else is
+            // still timing it
+            query(&format!("UPDATE tasks SET status = 'pending', updated_at = $1 WHERE id = $2 AND {}", NO_OTHER_TIMER))
+                .bind(now).bind(&task_id).execute(pool.get_ref()).await.ok();
 
             HttpResponse::Ok().json(serde_json::json!({
                 "stopped": true,
                 "task_id": task_id,
                 "duration_minutes": duration_minutes,
-                "entry_id": entry_id
+                "entry_id": entry_id,
+                // Shorter than the rounding policy's minimum: no entry saved
+                "discarded": entry_id.is_none()
             }))
         }
         None => HttpResponse::Ok().json(serde_json::json!({"stopped": false, "message": "No active timer"}))
@@ -145,35 +212,86 @@ pub async fn get_active_timer(pool: web::Data<DbPool>, req: HttpRequest) -> impl
     };
     let now = Utc::now();
 
-    let result: Option<(String, String, String, String, String, String)> = query_as(
-        "SELECT at.id, at.task_id, t.title, at.start_time, at.notes, at.created_at
-         FROM active_timers at
-         JOIN tasks t ON t.id = at.task_id
-         WHERE at.user_id = $1
-         LIMIT 1"
+    match fetch_timer(pool.get_ref(), &user_id).await {
+        Some(mut timer) => {
+            timer.elapsed_seconds = timer.elapsed_at(now);
+
+            HttpResponse::Ok().json(serde_json::json!({"active": true, "timer": timer}))
+        }
+        None => HttpResponse::Ok().json(serde_json::json!({"active": false, "timer": null}))
+    }
+}
+
+// ─── POST /api/timer/pause ───
+
+pub async fn pause_timer(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let now = Utc::now();
+
+    let result = query("UPDATE active_timers SET paused_at = $1 WHERE user_id = $2 AND paused_at IS NULL")
+        .bind(now)
+        .bind(&user_id)
+        .execute(pool.get_ref())
+        .await;
+
+    let paused = match result {
+        Ok(res) => res.rows_affected() > 0,
+        Err(e) => {
+            eprintln!("pause_timer error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match fetch_timer(pool.get_ref(), &user_id).await {
+        Some(mut timer) if paused => {
+            timer.elapsed_seconds = timer.elapsed_at(now);
+            HttpResponse::Ok().json(serde_json::json!({"active": true, "timer": timer}))
+        }
+        Some(_) => HttpResponse::Conflict().body("Timer is already paused"),
+        None => HttpResponse::NotFound().body("No active timer"),
+    }
+}
+
+// ─── POST /api/timer/resume ───
+
+pub async fn resume_timer(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let now = Utc::now();
+
+    // Fold the open pause into paused_seconds
+    let result = query(
+        "UPDATE active_timers
+         SET paused_seconds = paused_seconds + GREATEST(FLOOR(EXTRACT(EPOCH FROM ($1 - paused_at))), 0)::BIGINT,
+             paused_at = NULL
+         WHERE user_id = $2 AND paused_at IS NOT NULL"
     )
+    .bind(now)
     .bind(&user_id)
-    .fetch_optional(pool.get_ref())
-    .await
-    .unwrap_or(None);
-
-    match result {
-        Some((id, task_id, task_title, start_time_str, notes, created_at)) => {
-            let start: DateTime<Utc> = start_time_str.parse().unwrap_or(now);
-            let elapsed = (now - start).num_seconds().max(0);
-
-            let timer = ActiveTimer {
-                id,
-                task_id,
-                task_title,
-                start_time: start_time_str,
-                notes,
-                elapsed_seconds: elapsed,
-                created_at,
-            };
+    .execute(pool.get_ref())
+    .await;
 
+    let resumed = match result {
+        Ok(res) => res.rows_affected() > 0,
+        Err(e) => {
+            eprintln!("resume_timer error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match fetch_timer(pool.get_ref(), &user_id).await {
+        Some(mut timer) if resumed => {
+            timer.elapsed_seconds = timer.elapsed_at(now);
             HttpResponse::Ok().json(serde_json::json!({"active": true, "timer": timer}))
         }
-        None => HttpResponse::Ok().json(serde_json::json!({"active": false, "timer": null}))
+        Some(_) => HttpResponse::Conflict().body("Timer is not paused"),
+        None => HttpResponse::NotFound().body("No active timer"),
     }
 }
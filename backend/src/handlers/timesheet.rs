@@ -0,0 +1,326 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::{Datelike, Duration, Utc};
+use sqlx::{query, query_as, query_scalar, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::models::timesheet::*;
+use crate::models::user::AuthClaims;
+use crate::pagination::csv;
+use crate::rbac::{self, Grants};
+use crate::tz;
+
+// Totals are computed live from the entries in the period, on tasks of the
+// timesheet's workspace
+const TIMESHEET_SELECT: &str = "SELECT ts.*, u.username, tot.total_minutes, tot.entry_count
+     FROM timesheets ts
+     JOIN users u ON u.id = ts.user_id
+     CROSS JOIN LATERAL (
+         SELECT ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT AS total_minutes, COUNT(e.id)::BIGINT AS entry_count
+         FROM time_entries e JOIN tasks t ON t.id = e.task_id
+         WHERE e.user_id = ts.user_id AND t.workspace_id = ts.workspace_id
+           AND e.start_time >= ts.period_start AND e.start_time < ts.period_end
+     ) tot";
+
+fn caller(req: &HttpRequest) -> Option<(String, String)> {
+    req.extensions().get::<AuthClaims>().map(|c| (c.sub.clone(), c.wid.clone()))
+}
+
+// Same reach as user management: reviewers handle members of lower-level
+// roles, and their own level with users.manage_peers
+async fn manages(pool: &DbPool, grants: &Grants, owner_id: &str) -> bool {
+    let level: Option<i32> = query_scalar(
+        "SELECT r.level FROM workspace_members m JOIN roles r ON r.name = m.role AND (r.workspace_id IS NULL OR r.workspace_id = m.workspace_id)
+         WHERE m.workspace_id = $1 AND m.user_id = $2"
+    )
+    .bind(&grants.workspace_id)
+    .bind(owner_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    grants.has(rbac::TIMESHEETS_REVIEW) && level.is_some_and(|level| grants.reaches(level, true))
+}
+
+async fn load_detail(pool: &DbPool, id: &str) -> Result<Option<TimesheetDetail>, sqlx::Error> {
+    let timesheet = query_as::<_, Timesheet>(&format!("{} WHERE ts.id = $1", TIMESHEET_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    let Some(timesheet) = timesheet else { return Ok(None) };
+
+    let history = query_as::<_, TimesheetEvent>(
+        "SELECT ev.id, ev.actor_id, u.username AS actor_username, ev.action, ev.status, ev.comment, ev.created_at
+         FROM timesheet_events ev
+         LEFT JOIN users u ON u.id = ev.actor_id
+         WHERE ev.timesheet_id = $1
+         ORDER BY ev.id"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(TimesheetDetail { timesheet, history }))
+}
+
+// ─── GET /api/timesheets ───
+
+pub async fn get_timesheets(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<TimesheetListQuery>) -> impl Responder {
+    let Some((user_id, workspace_id)) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(TIMESHEET_SELECT);
+    qb.push(" WHERE ts.user_id = ").push_bind(user_id);
+    qb.push(" AND ts.workspace_id = ").push_bind(workspace_id);
+    if let Some(v) = csv(&query.status) { qb.push(" AND ts.status = ANY(").push_bind(v).push(")"); }
+    qb.push(" ORDER BY ts.week_start DESC");
+
+    match qb.build_query_as::<Timesheet>().fetch_all(pool.get_ref()).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_timesheets error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/timesheets ───
+
+// Submits a week for approval; a rejected or reopened week can be
+// submitted again
+pub async fn submit_timesheet(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<SubmitTimesheetRequest>) -> impl Responder {
+    let Some((user_id, workspace_id)) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    let zone = match tz::resolve(db, &user_id, body.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    let day = body.week.unwrap_or_else(|| tz::today(zone));
+    let week_start = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+    let period_start = tz::start_of_day(zone, week_start);
+    let period_end = tz::start_of_day(zone, week_start + Duration::days(7));
+    let now = Utc::now();
+
+    let result: Result<Option<String>, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+
+        let id: Option<String> = query_scalar(
+            "INSERT INTO timesheets (id, user_id, week_start, period_start, period_end, timezone, status, submitted_at, created_at, updated_at, workspace_id)
+             VALUES ($1, $2, $3, $4, $5, $6, 'submitted', $7, $7, $7, $8)
+             ON CONFLICT (workspace_id, user_id, week_start) DO UPDATE SET
+                 period_start = EXCLUDED.period_start,
+                 period_end = EXCLUDED.period_end,
+                 timezone = EXCLUDED.timezone,
+                 status = 'submitted',
+                 comment = '',
+                 submitted_at = EXCLUDED.submitted_at,
+                 decided_by = NULL,
+                 decided_at = NULL,
+                 updated_at = EXCLUDED.updated_at
+             WHERE timesheets.status IN ('rejected', 'reopened')
+             RETURNING id"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&user_id)
+        .bind(week_start)
+        .bind(period_start)
+        .bind(period_end)
+        .bind(zone.name())
+        .bind(now)
+        .bind(&workspace_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(id) = id else { return Ok(None) };
+
+        query("INSERT INTO timesheet_events (timesheet_id, actor_id, action, status, created_at) VALUES ($1, $2, 'submit', 'submitted', $3)")
+            .bind(&id)
+            .bind(&user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(id))
+    }
+    .await;
+
+    let id = match result {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            let status: Option<String> = query_scalar("SELECT status FROM timesheets WHERE workspace_id = $1 AND user_id = $2 AND week_start = $3")
+                .bind(&workspace_id).bind(&user_id).bind(week_start).fetch_optional(db).await.unwrap_or(None);
+            return HttpResponse::Conflict().body(format!("Week of {} is already {}", week_start, status.unwrap_or_default()));
+        }
+        Err(e) => {
+            eprintln!("submit_timesheet error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    match load_detail(db, &id).await {
+        Ok(Some(detail)) => HttpResponse::Created().json(detail),
+        _ => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── GET /api/timesheets/{id} ───
+
+pub async fn get_timesheet(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let (Some((user_id, _)), Some(grants)) = (caller(&req), rbac::grants(&req)) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    let detail = match load_detail(db, &path.into_inner()).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("get_timesheet error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if detail.timesheet.workspace_id != grants.workspace_id
+        || (detail.timesheet.user_id != user_id && !manages(db, &grants, &detail.timesheet.user_id).await)
+    {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::Ok().json(detail)
+}
+
+// ─── POST /api/timesheets/{id}/approve | reject | reopen ───
+
+pub async fn approve_timesheet(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<TimesheetDecisionRequest>) -> impl Responder {
+    decide(pool.get_ref(), &req, &path.into_inner(), "approve", body.into_inner()).await
+}
+
+pub async fn reject_timesheet(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<TimesheetDecisionRequest>) -> impl Responder {
+    decide(pool.get_ref(), &req, &path.into_inner(), "reject", body.into_inner()).await
+}
+
+// Unlocks an approved week so its owner can correct and resubmit it
+pub async fn reopen_timesheet(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>, body: web::Json<TimesheetDecisionRequest>) -> impl Responder {
+    decide(pool.get_ref(), &req, &path.into_inner(), "reopen", body.into_inner()).await
+}
+
+async fn decide(db: &DbPool, req: &HttpRequest, id: &str, action: &str, body: TimesheetDecisionRequest) -> HttpResponse {
+    let (Some((user_id, _)), Some(grants)) = (caller(req), rbac::grants(req)) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let (from, to) = match action {
+        "approve" => ("submitted", "approved"),
+        "reject" => ("submitted", "rejected"),
+        _ => ("approved", "reopened"),
+    };
+    let comment = body.comment.as_deref().map(str::trim).unwrap_or_default().to_string();
+    if action == "reject" && comment.is_empty() {
+        return HttpResponse::BadRequest().body("A comment is required to reject a timesheet");
+    }
+
+    let owner: Option<String> = query_scalar("SELECT user_id FROM timesheets WHERE id = $1 AND workspace_id = $2")
+        .bind(id)
+        .bind(&grants.workspace_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    let Some(owner_id) = owner else {
+        return HttpResponse::NotFound().finish();
+    };
+    // Someone else signs off on a week, whatever the reviewer's reach
+    if owner_id == user_id {
+        return HttpResponse::Forbidden().body("You cannot decide on your own timesheet");
+    }
+    if !manages(db, &grants, &owner_id).await {
+        return HttpResponse::Forbidden().body("Cannot decide on timesheets of users with this role");
+    }
+
+    let now = Utc::now();
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+
+        // The status guard makes concurrent decisions on the same week
+        // resolve to one winner
+        let updated = query(
+            "UPDATE timesheets SET status = $1, comment = $2, decided_by = $3, decided_at = $4, updated_at = $4
+             WHERE id = $5 AND status = $6"
+        )
+        .bind(to)
+        .bind(&comment)
+        .bind(&user_id)
+        .bind(now)
+        .bind(id)
+        .bind(from)
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        query("INSERT INTO timesheet_events (timesheet_id, actor_id, action, status, comment, created_at) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(id)
+            .bind(&user_id)
+            .bind(action)
+            .bind(to)
+            .bind(&comment)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => {
+            let status: Option<String> = query_scalar("SELECT status FROM timesheets WHERE id = $1")
+                .bind(id).fetch_optional(db).await.unwrap_or(None);
+            return HttpResponse::Conflict().body(format!("Cannot {} a timesheet that is {}", action, status.unwrap_or_default()));
+        }
+        Err(e) => {
+            eprintln!("decide_timesheet error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match load_detail(db, id).await {
+        Ok(Some(detail)) => HttpResponse::Ok().json(detail),
+        _ => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// ─── GET /api/admin/timesheets ───
+
+pub async fn get_timesheets_admin(pool: web::Data<DbPool>, req: HttpRequest, query: web::Query<TimesheetListQuery>) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let users = match rbac::reachable_users(pool.get_ref(), &grants).await {
+        Ok(users) => users,
+        Err(e) => {
+            eprintln!("get_timesheets_admin error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let mut qb = QueryBuilder::<Postgres>::new(TIMESHEET_SELECT);
+    qb.push(" WHERE ts.workspace_id = ").push_bind(grants.workspace_id.clone());
+    qb.push(" AND ts.user_id = ANY(").push_bind(users).push(")");
+    if let Some(v) = csv(&query.status)  { qb.push(" AND ts.status = ANY(").push_bind(v).push(")"); }
+    if let Some(v) = csv(&query.user_id) { qb.push(" AND ts.user_id = ANY(").push_bind(v).push(")"); }
+    qb.push(" ORDER BY ts.week_start DESC, u.username");
+
+    match qb.build_query_as::<Timesheet>().fetch_all(pool.get_ref()).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_timesheets_admin error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{query, query_as};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::models::token::*;
+use crate::models::user::AuthClaims;
+use crate::session;
+
+const TOKEN_SCOPES: [&str; 2] = ["read", "write"];
+
+const TOKEN_COLUMNS: &str = "id, name, token_prefix, scope, created_at, last_used_at, expires_at";
+
+// ─── GET /api/me/tokens ───
+
+pub async fn get_tokens(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    // Expired tokens stay listed so they can be told apart and revoked
+    let tokens = query_as::<_, PersonalAccessToken>(&format!(
+        "SELECT {} FROM personal_access_tokens WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at DESC",
+        TOKEN_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match tokens {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => {
+            eprintln!("get_tokens error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/me/tokens ───
+
+pub async fn create_token(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<CreateTokenRequest>) -> impl Responder {
+    let (user_id, via_token) = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        (claims.sub.clone(), claims.scope.is_some())
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    // A leaked token must not be able to mint more
+    if via_token {
+        return HttpResponse::Forbidden().body("Tokens can only be created from a signed-in session");
+    }
+
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return HttpResponse::BadRequest().body("name must be 1 to 100 characters");
+    }
+    let scope = body.scope.as_deref().unwrap_or("read");
+    if !TOKEN_SCOPES.contains(&scope) {
+        return HttpResponse::BadRequest().body(format!("scope must be one of {}", TOKEN_SCOPES.join(", ")));
+    }
+    let now = Utc::now();
+    if body.expires_at.is_some_and(|at| at <= now) {
+        return HttpResponse::BadRequest().body("expires_at must be in the future");
+    }
+
+    let token = format!("{}{}", session::PAT_PREFIX, session::random_token());
+    let info = query_as::<_, PersonalAccessToken>(&format!(
+        "INSERT INTO personal_access_tokens (id, user_id, name, token_hash, token_prefix, scope, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        TOKEN_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user_id)
+    .bind(name)
+    .bind(session::hash_token(&token))
+    .bind(token.chars().take(12).collect::<String>())
+    .bind(scope)
+    .bind(now)
+    .bind(body.expires_at)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match info {
+        Ok(info) => HttpResponse::Created().json(CreatedToken { info, token }),
+        Err(e) => {
+            eprintln!("create_token error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── DELETE /api/me/tokens/{id} ───
+
+pub async fn revoke_token(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let user_id = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.sub.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let result = query("UPDATE personal_access_tokens SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL")
+        .bind(path.into_inner())
+        .bind(user_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 0 => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({"revoked": true})),
+        Err(e) => {
+            eprintln!("revoke_token error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use argon2::{password_hash::{PasswordHash, PasswordVerifier}, Argon2};
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::DbPool;
+use crate::models::two_factor::*;
+use crate::models::user::AuthClaims;
+use crate::rbac;
+use crate::two_factor;
+
+// 2FA is managed from a signed-in session only, never with an access token
+fn session_caller(req: &HttpRequest) -> Result<AuthClaims, HttpResponse> {
+    match req.extensions().get::<AuthClaims>() {
+        Some(c) if c.scope.is_some() => {
+            Err(HttpResponse::Forbidden().body("Two-factor authentication can only be managed from a signed-in session"))
+        }
+        Some(c) => Ok(c.clone()),
+        None => Err(HttpResponse::Unauthorized().finish()),
+    }
+}
+
+async fn issue_recovery_codes(pool: &DbPool, user_id: &str) -> HttpResponse {
+    match two_factor::new_recovery_codes(pool, user_id).await {
+        Ok(recovery_codes) => HttpResponse::Ok().json(RecoveryCodes { recovery_codes }),
+        Err(e) => {
+            eprintln!("recovery codes error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn clear(pool: &DbPool, user_id: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    query("UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_last_step = 0 WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    query("DELETE FROM recovery_codes WHERE user_id = $1").bind(user_id).execute(&mut *tx).await?;
+    query("DELETE FROM login_challenges WHERE user_id = $1").bind(user_id).execute(&mut *tx).await?;
+    tx.commit().await
+}
+
+// ─── GET /api/me/2fa ───
+
+pub async fn get_status(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let claims = if let Some(claims) = req.extensions().get::<AuthClaims>() {
+        claims.clone()
+    } else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let row: Result<(bool, i64), sqlx::Error> = query_as(
+        "SELECT u.totp_enabled, (SELECT COUNT(*) FROM recovery_codes r WHERE r.user_id = u.id AND r.used_at IS NULL)
+         FROM users u WHERE u.id = $1"
+    )
+    .bind(&claims.sub)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match row {
+        Ok((enabled, recovery_codes_remaining)) => HttpResponse::Ok().json(TwoFactorStatus {
+            enabled,
+            required: two_factor::required_for(pool.get_ref(), &claims.wid, &claims.role).await,
+            recovery_codes_remaining,
+        }),
+        Err(e) => {
+            eprintln!("get_status error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/me/2fa/setup ───
+
+// Starts over with a new secret each time until it is confirmed
+pub async fn setup(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let claims = match session_caller(&req) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+
+    let user: Option<(String, bool)> = query_as("SELECT username, totp_enabled FROM users WHERE id = $1")
+        .bind(&claims.sub)
+        .fetch_optional(pool.get_ref())
+        .await
+        .unwrap_or(None);
+    let Some((username, enabled)) = user else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    if enabled {
+        return HttpResponse::Conflict().body("Two-factor authentication is already enabled");
+    }
+
+    let secret = two_factor::new_secret();
+    let Some(otpauth_url) = two_factor::provisioning_uri(&secret, &username) else {
+        return HttpResponse::InternalServerError().finish();
+    };
+    let result = query("UPDATE users SET totp_secret = $1, totp_last_step = 0 WHERE id = $2 AND NOT totp_enabled")
+        .bind(&secret)
+        .bind(&claims.sub)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(TwoFactorSetup { secret, otpauth_url }),
+        Err(e) => {
+            eprintln!("setup 2fa error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/me/2fa/confirm ───
+
+// Turns 2FA on once the app produces a valid code; returns the recovery codes
+pub async fn confirm(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<TwoFactorCodeRequest>) -> impl Responder {
+    let claims = match session_caller(&req) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let db = pool.get_ref();
+
+    let pending: Option<(Option<String>, bool)> = query_as("SELECT totp_secret, totp_enabled FROM users WHERE id = $1")
+        .bind(&claims.sub)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    let secret = match pending {
+        None => return HttpResponse::Unauthorized().finish(),
+        Some((_, true)) => return HttpResponse::Conflict().body("Two-factor authentication is already enabled"),
+        Some((None, false)) => return HttpResponse::BadRequest().body("Start with /api/me/2fa/setup"),
+        Some((Some(secret), false)) => secret,
+    };
+    let Some(step) = two_factor::verify(&secret, &body.code, 0) else {
+        return HttpResponse::BadRequest().body("Invalid code");
+    };
+
+    let result = query("UPDATE users SET totp_enabled = TRUE, totp_last_step = $1 WHERE id = $2 AND totp_secret = $3 AND NOT totp_enabled")
+        .bind(step)
+        .bind(&claims.sub)
+        .bind(&secret)
+        .execute(db)
+        .await;
+    match result {
+        Ok(r) if r.rows_affected() == 0 => return HttpResponse::Conflict().body("Setup changed meanwhile, start again"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("confirm 2fa error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    issue_recovery_codes(db, &claims.sub).await
+}
+
+// ─── POST /api/me/2fa/recovery-codes ───
+
+// New set of recovery codes; the old ones stop working
+pub async fn regenerate_recovery_codes(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    body: web::Json<TwoFactorCodeRequest>,
+) -> impl Responder {
+    let claims = match session_caller(&req) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let db = pool.get_ref();
+
+    match two_factor::check_code(db, &claims.sub, &body.code).await {
+        Ok(true) => issue_recovery_codes(db, &claims.sub).await,
+        Ok(false) => HttpResponse::BadRequest().body("Invalid code or two-factor authentication not enabled"),
+        Err(e) => {
+            eprintln!("regenerate_recovery_codes error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/me/2fa/disable ───
+
+pub async fn disable(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<DisableTwoFactorRequest>) -> impl Responder {
+    let claims = match session_caller(&req) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let db = pool.get_ref();
+
+    if two_factor::required_for(db, &claims.wid, &claims.role).await {
+        return HttpResponse::Forbidden().body("Two-factor authentication is required for your role");
+    }
+
+    let password_hash: Option<String> = query_scalar("SELECT password_hash FROM users WHERE id = $1")
+        .bind(&claims.sub)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    let Some(password_hash) = password_hash else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let parsed_hash = PasswordHash::new(&password_hash).expect("Invalid hash in DB");
+    if Argon2::default().verify_password(body.password.as_bytes(), &parsed_hash).is_err() {
+        return HttpResponse::BadRequest().body("Invalid password");
+    }
+
+    match two_factor::check_code(db, &claims.sub, &body.code).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::BadRequest().body("Invalid code or two-factor authentication not enabled"),
+        Err(e) => {
+            eprintln!("disable 2fa error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match clear(db, &claims.sub).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            eprintln!("disable 2fa error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── DELETE /api/users/{id}/2fa ───
+
+// For users who lost their device and recovery codes. They set it up again
+// on next login if the workspace requires it. Only for users of this
+// workspace alone, as 2FA covers the whole account.
+pub async fn reset_user_2fa(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+
+    if let Err(resp) = rbac::account(pool.get_ref(), &grants, &user_id, true).await {
+        return resp;
+    }
+
+    match clear(pool.get_ref(), &user_id).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            eprintln!("reset_user_2fa error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
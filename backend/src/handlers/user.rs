@@ -4,37 +4,49 @@ use argon2::{
 };
 use serde::Serialize;
 use crate::{
+    audit::{self, Actor},
+    billing,
     db::DbPool,
-    models::user::{AuthClaims, ResetPasswordRequest, UpdateProfileRequest, UpdateRoleRequest, User},
+    models::user::{AuthClaims, ResetPasswordRequest, UpdateProfileRequest, UpdateRateRequest, UpdateRoleRequest, User},
+    pagination::csv,
+    rbac,
+    session,
+    tz,
 };
 use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use chrono::{Months, NaiveDate};
 use sqlx::query_as;
 
-// Helper: extract role from request
-fn caller_role(req: &HttpRequest) -> Option<String> {
-    req.extensions().get::<AuthClaims>().map(|c| c.role.clone())
-}
+// Users with their role and rate in workspace $1
+pub const MEMBER_SELECT: &str = "SELECT u.*, m.role, m.hourly_rate_cents
+     FROM users u JOIN workspace_members m ON m.user_id = u.id AND m.workspace_id = $1";
+
 fn caller_id(req: &HttpRequest) -> Option<String> {
     req.extensions().get::<AuthClaims>().map(|c| c.sub.clone())
 }
 
-// Admin/Superadmin: Get users (superadmin sees all, admin sees only users)
+pub async fn load_member(pool: &DbPool, workspace_id: &str, user_id: &str) -> Result<Option<User>, sqlx::Error> {
+    query_as::<_, User>(&format!("{} WHERE u.id = $2", MEMBER_SELECT))
+        .bind(workspace_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+// Members of the workspace the caller can manage: those of lower-level
+// roles, and their own level with users.manage_peers
 pub async fn get_users(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
-    let role = match caller_role(&req) {
-        Some(r) => r,
-        None => return HttpResponse::Unauthorized().finish(),
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
     };
 
-    let users = if role == "superadmin" {
-        query_as::<_, User>("SELECT * FROM users ORDER BY created_at DESC")
-            .fetch_all(pool.get_ref())
-            .await
-    } else if role == "admin" {
-        query_as::<_, User>("SELECT * FROM users WHERE role = 'user' ORDER BY created_at DESC")
+    let users = match rbac::reachable_users(pool.get_ref(), &grants).await {
+        Ok(ids) => query_as::<_, User>(&format!("{} WHERE u.id = ANY($2) ORDER BY u.created_at DESC", MEMBER_SELECT))
+            .bind(&grants.workspace_id)
+            .bind(ids)
             .fetch_all(pool.get_ref())
-            .await
-    } else {
-        return HttpResponse::Forbidden().finish();
+            .await,
+        Err(e) => Err(e),
     };
 
     match users {
@@ -43,51 +55,43 @@ pub async fn get_users(pool: web::Data<DbPool>, req: HttpRequest) -> impl Respon
     }
 }
 
-// Admin/Superadmin: Delete user (admin can only delete 'user' role)
+// Delete user: only those of lower-level roles, never peers, and only
+// when they belong to no other workspace
 pub async fn delete_user(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
-    let caller_role = match caller_role(&req) {
-        Some(r) => r,
-        None => return HttpResponse::Unauthorized().finish(),
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
     };
     let user_id = path.into_inner();
 
-    // Fetch target user's role
-    let target_role: Option<String> = sqlx::query_scalar("SELECT role FROM users WHERE id = $1")
-        .bind(&user_id)
-        .fetch_optional(pool.get_ref())
-        .await
-        .unwrap_or(None);
-
-    match target_role.as_deref() {
-        None => return HttpResponse::NotFound().finish(),
-        Some("superadmin") => return HttpResponse::Forbidden().body("Cannot delete superadmin"),
-        Some("admin") if caller_role != "superadmin" => {
-            return HttpResponse::Forbidden().body("Only superadmin can delete admin")
-        }
-        _ => {}
+    if let Err(resp) = rbac::account(pool.get_ref(), &grants, &user_id, false).await {
+        return resp;
     }
+    let before = audit::user(pool.get_ref(), &user_id).await;
 
     let result = sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(user_id)
+        .bind(&user_id)
         .execute(pool.get_ref())
         .await;
 
     match result {
-        Ok(_) => HttpResponse::Ok().finish(),
+        Ok(_) => {
+            audit::log(pool.get_ref(), &Actor::of(&req), "user.delete", "user", &user_id, before, None).await;
+            HttpResponse::Ok().finish()
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-// Admin/Superadmin: Update user role
+// Update user role in the workspace: for users of lower-level roles, to
+// any role of the workspace the caller could manage afterwards
 pub async fn update_role(
     pool: web::Data<DbPool>,
     req: HttpRequest,
     path: web::Path<String>,
     body: web::Json<UpdateRoleRequest>,
 ) -> impl Responder {
-    let caller_role = match caller_role(&req) {
-        Some(r) => r,
-        None => return HttpResponse::Unauthorized().finish(),
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
     };
     let caller_id = caller_id(&req).unwrap_or_default();
     let user_id = path.into_inner();
@@ -96,55 +100,102 @@ pub async fn update_role(
         return HttpResponse::BadRequest().body("Cannot change your own role");
     }
 
-    // Validate allowed roles
-    let allowed_roles = if caller_role == "superadmin" {
-        vec!["superadmin", "admin", "user"]
-    } else if caller_role == "admin" {
-        vec!["user"] // admin can only toggle between user roles
-    } else {
-        return HttpResponse::Forbidden().finish();
-    };
-
-    if !allowed_roles.contains(&body.role.as_str()) {
+    let new_level = assignable_level(pool.get_ref(), &grants, &body.role).await;
+    if !new_level.is_some_and(|level| grants.reaches(level, true)) {
         return HttpResponse::BadRequest().body("Invalid role or insufficient permissions");
     }
 
-    // Fetch target user's current role
-    let target_role: Option<String> = sqlx::query_scalar("SELECT role FROM users WHERE id = $1")
-        .bind(&user_id)
-        .fetch_optional(pool.get_ref())
-        .await
-        .unwrap_or(None);
-
-    match target_role.as_deref() {
-        None => return HttpResponse::NotFound().finish(),
-        Some("superadmin") => return HttpResponse::Forbidden().body("Cannot change superadmin role"),
-        Some("admin") if caller_role != "superadmin" => {
-            return HttpResponse::Forbidden().body("Only superadmin can change admin role")
-        }
-        _ => {}
-    }
+    let old_role = match rbac::target(pool.get_ref(), &grants, &user_id, false).await {
+        Ok(role) => role,
+        Err(resp) => return resp,
+    };
 
-    let result = sqlx::query("UPDATE users SET role = $1 WHERE id = $2")
+    let result = sqlx::query("UPDATE workspace_members SET role = $1 WHERE workspace_id = $2 AND user_id = $3")
         .bind(&body.role)
-        .bind(user_id)
+        .bind(&grants.workspace_id)
+        .bind(&user_id)
         .execute(pool.get_ref())
         .await;
 
     match result {
-        Ok(_) => HttpResponse::Ok().finish(),
+        Ok(_) => {
+            let (before, after) = (serde_json::json!({"role": old_role}), serde_json::json!({"role": body.role}));
+            audit::log(pool.get_ref(), &Actor::of(&req), "user.role_change", "user", &user_id, Some(before), Some(after)).await;
+            HttpResponse::Ok().finish()
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+// Level of `role` if it can be given out in the caller's workspace: built
+// in, or one of the workspace's own
+pub async fn assignable_level(pool: &DbPool, grants: &rbac::Grants, role: &str) -> Option<i32> {
+    sqlx::query_scalar("SELECT level FROM roles WHERE name = $1 AND (workspace_id IS NULL OR workspace_id = $2)")
+        .bind(role)
+        .bind(&grants.workspace_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+}
+
+// Set a user's hourly rate in the workspace
+pub async fn update_rate(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<UpdateRateRequest>,
+) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+
+    if body.hourly_rate_cents.is_some_and(|r| r < 0) {
+        return HttpResponse::BadRequest().body("hourly_rate_cents must not be negative");
+    }
+
+    if let Err(resp) = rbac::target(pool.get_ref(), &grants, &user_id, true).await {
+        return resp;
+    }
+
+    let old_rate: Result<Option<i64>, sqlx::Error> = sqlx::query_scalar(
+        "UPDATE workspace_members m SET hourly_rate_cents = $1 FROM workspace_members old
+         WHERE m.workspace_id = $2 AND m.user_id = $3 AND old.workspace_id = m.workspace_id AND old.user_id = m.user_id
+         RETURNING old.hourly_rate_cents"
+    )
+    .bind(body.hourly_rate_cents)
+    .bind(&grants.workspace_id)
+    .bind(&user_id)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match old_rate {
+        Ok(old_rate) => {
+            let before = serde_json::json!({"hourly_rate_cents": old_rate});
+            let after = serde_json::json!({"hourly_rate_cents": body.hourly_rate_cents});
+            audit::log(pool.get_ref(), &Actor::of(&req), "user.rate_change", "user", &user_id, Some(before), Some(after)).await;
+            HttpResponse::Ok().finish()
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-// Admin only: Reset password
+// Reset password; the user has to change it on next login
 pub async fn reset_password(
     pool: web::Data<DbPool>,
+    req: HttpRequest,
     path: web::Path<String>,
     body: web::Json<ResetPasswordRequest>,
 ) -> impl Responder {
+    let Some(grants) = rbac::grants(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
     let user_id = path.into_inner();
 
+    if let Err(resp) = rbac::account(pool.get_ref(), &grants, &user_id, true).await {
+        return resp;
+    }
+
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
     let password_hash = argon2
@@ -154,17 +205,25 @@ pub async fn reset_password(
 
     let result = sqlx::query("UPDATE users SET password_hash = $1, force_change_password = TRUE WHERE id = $2")
         .bind(password_hash)
-        .bind(user_id)
+        .bind(&user_id)
         .execute(pool.get_ref())
         .await;
 
-    match result {
-        Ok(_) => HttpResponse::Ok().finish(),
+    if result.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    // Whoever knew the old password is signed out everywhere
+    match session::revoke_all(pool.get_ref(), &user_id, None).await {
+        Ok(_) => {
+            audit::log(pool.get_ref(), &Actor::of(&req), "user.password_reset", "user", &user_id, None, None).await;
+            HttpResponse::Ok().finish()
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-// Current user: Update profile (full_name)
+// Current user: Update profile (full_name, timezone)
 pub async fn update_profile(
     pool: web::Data<DbPool>,
     req: HttpRequest,
@@ -178,11 +237,20 @@ pub async fn update_profile(
         }
     };
 
-    let result = sqlx::query("UPDATE users SET full_name = $1 WHERE id = $2")
-        .bind(&body.full_name)
-        .bind(&user_id)
-        .execute(pool.get_ref())
-        .await;
+    if let Some(ref name) = body.timezone {
+        if let Err(msg) = tz::parse(name) {
+            return HttpResponse::BadRequest().body(msg);
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE users SET full_name = COALESCE($1, full_name), timezone = COALESCE($2, timezone) WHERE id = $3",
+    )
+    .bind(&body.full_name)
+    .bind(&body.timezone)
+    .bind(&user_id)
+    .execute(pool.get_ref())
+    .await;
 
     match result {
         Ok(_) => HttpResponse::Ok().finish(),
@@ -190,7 +258,7 @@ pub async fn update_profile(
     }
 }
 
-// Admin only: Time report — total minutes per user per project (monthly + all-time)
+// Time report — total minutes per user per project or tag (monthly + all-time)
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct TimeReportRow {
     pub user_id: String,
@@ -199,13 +267,24 @@ pub struct TimeReportRow {
     pub project_id: Option<String>,
     pub project_name: Option<String>,
     pub project_color: Option<String>,
+    pub tag_id: Option<String>,
+    pub tag_name: Option<String>,
+    pub tag_color: Option<String>,
     pub minutes_this_period: i64,
     pub minutes_all_time: i64,
+    pub seconds_this_period: i64,
+    pub seconds_all_time: i64,
+    pub billable_minutes_this_period: i64,
+    pub amount_this_period: i64, // minor units of the workspace currency
+    pub amount_all_time: i64,
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct TimeReportQuery {
     pub month: Option<String>, // format: "YYYY-MM", default = current month
+    pub tz: Option<String>,    // IANA name, defaults to the caller's timezone
+    pub tag_id: Option<String>, // comma-separated; only count entries on tasks with these tags
+    pub group_by: Option<String>, // "project" (default) or "tag"
 }
 
 pub async fn get_time_report_admin(
@@ -213,64 +292,101 @@ pub async fn get_time_report_admin(
     req: HttpRequest,
     query: web::Query<TimeReportQuery>,
 ) -> impl Responder {
-    let role = {
-        let ext = req.extensions();
-        match ext.get::<AuthClaims>() {
-            Some(c) => c.role.clone(),
-            None => return HttpResponse::Unauthorized().finish(),
-        }
+    let (Some(caller_id), Some(grants)) = (caller_id(&req), rbac::grants(&req)) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    // Month boundaries follow the report timezone, not UTC
+    let zone = match tz::resolve(pool.get_ref(), &caller_id, query.tz.as_deref()).await {
+        Ok(z) => z,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
     };
-    if role != "admin" && role != "superadmin" {
-        return HttpResponse::Forbidden().body("Admin only");
-    }
 
     // Determine month filter — default to current month
     let month = query.month.clone().unwrap_or_else(|| {
-        chrono::Utc::now().format("%Y-%m").to_string()
+        tz::today(zone).format("%Y-%m").to_string()
     });
 
     // Parse month into start/end timestamps
-    let month_start = format!("{}-01T00:00:00Z", month);
-    let month_end = {
-        let parts: Vec<&str> = month.split('-').collect();
-        if parts.len() == 2 {
-            let year: i32 = parts[0].parse().unwrap_or(2026);
-            let mon: u32 = parts[1].parse().unwrap_or(1);
-            let (next_year, next_month) = if mon == 12 { (year + 1, 1) } else { (year, mon + 1) };
-            format!("{:04}-{:02}-01T00:00:00Z", next_year, next_month)
-        } else {
-            format!("{}-01T00:00:00Z", month)
+    let first_day = match NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => return HttpResponse::BadRequest().body("month must be formatted as YYYY-MM"),
+    };
+    let month_start = tz::start_of_day(zone, first_day);
+    let month_end = tz::start_of_day(zone, first_day + Months::new(1));
+
+    let members = match rbac::reachable_users(pool.get_ref(), &grants).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("get_time_report_admin error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
         }
     };
 
-    let role_filter = if role == "superadmin" { "" } else { "AND u.role = 'user'" };
+    // A task with several tags counts towards each of them in tag mode
+    let (group_cols, group_join, group_by) = match query.group_by.as_deref().unwrap_or("project") {
+        "project" => (
+            "p.id AS project_id, p.name AS project_name, p.color AS project_color,
+             NULL::TEXT AS tag_id, NULL::TEXT AS tag_name, NULL::TEXT AS tag_color",
+            "",
+            "p.id, p.name, p.color",
+        ),
+        "tag" => (
+            "NULL::TEXT AS project_id, NULL::TEXT AS project_name, NULL::TEXT AS project_color,
+             tg.id AS tag_id, tg.name AS tag_name, tg.color AS tag_color",
+            "LEFT JOIN task_tags tt ON tt.task_id = t.id LEFT JOIN tags tg ON tg.id = tt.tag_id",
+            "tg.id, tg.name, tg.color",
+        ),
+        other => return HttpResponse::BadRequest().body(format!("Unknown group_by: {}", other)),
+    };
 
+    const IN_PERIOD: &str = "e.start_time >= $1 AND e.start_time < $2";
     let sql = format!(
         "SELECT u.id AS user_id, u.username, u.full_name,
-                p.id AS project_id, p.name AS project_name, p.color AS project_color,
-                COALESCE(SUM(CASE
-                    WHEN e.start_time >= $1 AND e.start_time < $2
-                    THEN e.duration_minutes ELSE 0
-                END), 0)::BIGINT AS minutes_this_period,
-                COALESCE(SUM(e.duration_minutes), 0)::BIGINT AS minutes_all_time
+                {group_cols},
+                ROUND(COALESCE(SUM(CASE
+                    WHEN {in_period}
+                    THEN e.duration_seconds ELSE 0
+                END), 0) / 60.0)::BIGINT AS minutes_this_period,
+                ROUND(COALESCE(SUM(e.duration_seconds), 0) / 60.0)::BIGINT AS minutes_all_time,
+                COALESCE(SUM(CASE WHEN {in_period} THEN e.duration_seconds ELSE 0 END), 0)::BIGINT AS seconds_this_period,
+                COALESCE(SUM(e.duration_seconds), 0)::BIGINT AS seconds_all_time,
+                {billable_this_period} AS billable_minutes_this_period,
+                {amount_this_period} AS amount_this_period,
+                {amount_all_time} AS amount_all_time
          FROM users u
          LEFT JOIN time_entries e ON e.user_id = u.id
+              AND EXISTS (SELECT 1 FROM tasks wt WHERE wt.id = e.task_id AND wt.workspace_id = $5)
+              AND ($3::TEXT[] IS NULL OR EXISTS (
+                  SELECT 1 FROM task_tags ft WHERE ft.task_id = e.task_id AND ft.tag_id = ANY($3)))
          LEFT JOIN tasks t ON t.id = e.task_id
          LEFT JOIN projects p ON p.id = t.project_id
-         WHERE 1=1 {role_filter}
-         GROUP BY u.id, u.username, u.full_name, p.id, p.name, p.color
-         ORDER BY u.username, minutes_all_time DESC"
+         {group_join}
+         WHERE u.id = ANY($4)
+         GROUP BY u.id, u.username, u.full_name, {group_by}
+         ORDER BY u.username, minutes_all_time DESC",
+        in_period = IN_PERIOD,
+        billable_this_period = billing::billable_minutes_sql(IN_PERIOD),
+        amount_this_period = billing::amount_sql(IN_PERIOD),
+        amount_all_time = billing::amount_sql("TRUE"),
     );
 
     let result = sqlx::query_as::<_, TimeReportRow>(&sql)
-        .bind(&month_start)
-        .bind(&month_end)
+        .bind(month_start)
+        .bind(month_end)
+        .bind(csv(&query.tag_id))
+        .bind(members)
+        .bind(&grants.workspace_id)
         .fetch_all(pool.get_ref())
         .await;
 
+    let currency = billing::currency(pool.get_ref(), &grants.workspace_id).await;
+
     match result {
         Ok(rows) => HttpResponse::Ok().json(serde_json::json!({
             "month": month,
+            "timezone": zone.name(),
+            "currency": currency,
             "rows": rows,
         })),
         Err(e) => {
@@ -0,0 +1,361 @@
+use actix_web::{web, HttpRequest, HttpMessage, HttpResponse, Responder};
+use sqlx::{query, query_as, query_scalar, Postgres};
+use uuid::Uuid;
+
+use crate::audit::{self, Actor};
+use crate::db::DbPool;
+use crate::handlers::user::assignable_level;
+use crate::models::user::AuthClaims;
+use crate::models::workspace::*;
+use crate::rbac;
+use crate::session;
+
+const WORKSPACE_SELECT: &str = "SELECT w.id, w.name, m.role, w.created_at, w.id = $2 AS current,
+            (SELECT COUNT(*) FROM workspace_members c WHERE c.workspace_id = w.id) AS member_count
+     FROM workspace_members m
+     JOIN workspaces w ON w.id = m.workspace_id
+     WHERE m.user_id = $1";
+
+fn caller(req: &HttpRequest) -> Option<AuthClaims> {
+    req.extensions().get::<AuthClaims>().cloned()
+}
+
+// A new workspace with default settings and `owner_id` as its superadmin
+pub async fn create_workspace(tx: &mut sqlx::Transaction<'_, Postgres>, name: &str, owner_id: &str) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    query("INSERT INTO workspaces (id, name) VALUES ($1, $2)")
+        .bind(&id)
+        .bind(name)
+        .execute(&mut **tx)
+        .await?;
+    query("INSERT INTO workspace_settings (workspace_id) VALUES ($1)")
+        .bind(&id)
+        .execute(&mut **tx)
+        .await?;
+    query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES ($1, $2, 'superadmin')")
+        .bind(&id)
+        .bind(owner_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(id)
+}
+
+// ─── GET /api/workspaces ───
+
+pub async fn get_workspaces(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let rows = query_as::<_, Workspace>(&format!("{} ORDER BY m.created_at, w.name", WORKSPACE_SELECT))
+        .bind(&claims.sub)
+        .bind(&claims.wid)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match rows {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            eprintln!("get_workspaces error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/workspaces ───
+
+// Anyone can start a workspace of their own; they become its superadmin
+pub async fn create(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<CreateWorkspaceRequest>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return HttpResponse::BadRequest().body("name must be 1 to 100 characters");
+    }
+    let db = pool.get_ref();
+
+    let result: Result<String, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        let id = create_workspace(&mut tx, name, &claims.sub).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+    .await;
+    let id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("create_workspace error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let workspace = query_as::<_, Workspace>(&format!("{} AND w.id = $3", WORKSPACE_SELECT))
+        .bind(&claims.sub)
+        .bind(&claims.wid)
+        .bind(&id)
+        .fetch_one(db)
+        .await;
+    match workspace {
+        Ok(workspace) => {
+            let actor = Actor { workspace_id: Some(id.clone()), ..Actor::of(&req) };
+            audit::log(db, &actor, "workspace.create", "workspace", &id, None, audit::json(&workspace)).await;
+            HttpResponse::Created().json(workspace)
+        }
+        Err(e) => {
+            eprintln!("create_workspace error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/workspaces/{id}/switch ───
+
+// Moves the session to another workspace. Personal access tokens have no
+// session to move; they pick a workspace per request with X-Workspace-Id.
+pub async fn switch(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let claims = match caller(&req) {
+        Some(c) if c.scope.is_some() => {
+            return HttpResponse::Forbidden().body("Personal access tokens select a workspace with the X-Workspace-Id header")
+        }
+        Some(c) => c,
+        None => return HttpResponse::Unauthorized().finish(),
+    };
+    let workspace_id = path.into_inner();
+
+    match session::switch(pool.get_ref(), &claims, &workspace_id).await {
+        Ok(Some(token)) => HttpResponse::Ok().json(serde_json::json!({
+            "token": token,
+            "expires_in": session::ACCESS_TOKEN_MINUTES * 60,
+            "workspace_id": workspace_id,
+        })),
+        Ok(None) => HttpResponse::Forbidden().body("Not a member of this workspace"),
+        Err(e) => {
+            eprintln!("switch_workspace error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/users ───
+
+// Invites an existing account into the active workspace; they join once
+// they accept. Only roles the caller could also assign with PUT
+// /api/users/{id}/role. Unknown usernames and existing members get the
+// same answer, so this can't be used to look up accounts.
+pub async fn add_member(pool: web::Data<DbPool>, req: HttpRequest, body: web::Json<AddMemberRequest>) -> impl Responder {
+    let (Some(claims), Some(grants)) = (caller(&req), rbac::grants(&req)) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let db = pool.get_ref();
+
+    let level = assignable_level(db, &grants, &body.role).await;
+    if !level.is_some_and(|level| grants.reaches(level, true)) {
+        return HttpResponse::BadRequest().body("Invalid role or insufficient permissions");
+    }
+
+    let user_id: Option<String> = query_scalar(
+        "SELECT u.id FROM users u WHERE u.username = $1
+         AND NOT EXISTS (SELECT 1 FROM workspace_members m WHERE m.workspace_id = $2 AND m.user_id = u.id)"
+    )
+    .bind(body.username.trim())
+    .bind(&grants.workspace_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+
+    if let Some(user_id) = user_id {
+        // Inviting again replaces the pending invite's role
+        let invited = query(
+            "INSERT INTO workspace_invites (id, workspace_id, user_id, role, invited_by) VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (workspace_id, user_id) DO UPDATE
+             SET role = EXCLUDED.role, invited_by = EXCLUDED.invited_by, created_at = NOW()"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&grants.workspace_id)
+        .bind(&user_id)
+        .bind(&body.role)
+        .bind(&claims.sub)
+        .execute(db)
+        .await;
+        if let Err(e) = invited {
+            eprintln!("add_member error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        let after = serde_json::json!({"role": body.role});
+        audit::log(db, &Actor::of(&req), "workspace.member_invite", "user", &user_id, None, Some(after)).await;
+    }
+
+    HttpResponse::Accepted().json(serde_json::json!({"invited": true}))
+}
+
+// ─── GET /api/workspaces/invites ───
+
+pub async fn get_invites(pool: web::Data<DbPool>, req: HttpRequest) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let invites = query_as::<_, WorkspaceInvite>(
+        "SELECT i.id, i.workspace_id, w.name AS workspace_name, i.role, u.username AS invited_by, i.created_at
+         FROM workspace_invites i
+         JOIN workspaces w ON w.id = i.workspace_id
+         LEFT JOIN users u ON u.id = i.invited_by
+         WHERE i.user_id = $1
+         ORDER BY i.created_at DESC"
+    )
+    .bind(&claims.sub)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match invites {
+        Ok(invites) => HttpResponse::Ok().json(invites),
+        Err(e) => {
+            eprintln!("get_invites error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── POST /api/workspaces/invites/{id}/accept ───
+
+// Joins the workspace with the invited role, unless that role has been
+// deleted since; the invite is used up either way
+pub async fn accept_invite(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let id = path.into_inner();
+    let db = pool.get_ref();
+
+    let result: Result<Option<(String, String, bool)>, sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        let invite: Option<(String, String)> = query_as(
+            "DELETE FROM workspace_invites WHERE id = $1 AND user_id = $2 RETURNING workspace_id, role"
+        )
+        .bind(&id)
+        .bind(&claims.sub)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((workspace_id, role)) = invite else {
+            return Ok(None);
+        };
+        let joined = query(
+            "INSERT INTO workspace_members (workspace_id, user_id, role)
+             SELECT $1, $2, r.name FROM roles r WHERE r.name = $3 AND (r.workspace_id IS NULL OR r.workspace_id = $1)
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(&workspace_id)
+        .bind(&claims.sub)
+        .bind(&role)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some((workspace_id, role, joined.rows_affected() > 0)))
+    }
+    .await;
+
+    let (workspace_id, role) = match result {
+        Ok(Some((workspace_id, role, true))) => (workspace_id, role),
+        Ok(Some((_, _, false))) => return HttpResponse::Gone().body("The invited role no longer exists"),
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("accept_invite error: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let actor = Actor { workspace_id: Some(workspace_id.clone()), ..Actor::of(&req) };
+    let after = serde_json::json!({"role": role});
+    audit::log(db, &actor, "workspace.member_add", "user", &claims.sub, None, Some(after)).await;
+
+    let workspace = query_as::<_, Workspace>(&format!("{} AND w.id = $3", WORKSPACE_SELECT))
+        .bind(&claims.sub)
+        .bind(&claims.wid)
+        .bind(&workspace_id)
+        .fetch_one(db)
+        .await;
+    match workspace {
+        Ok(workspace) => HttpResponse::Ok().json(workspace),
+        Err(e) => {
+            eprintln!("accept_invite error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── DELETE /api/workspaces/invites/{id} ───
+
+pub async fn decline_invite(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let Some(claims) = caller(&req) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let declined = query("DELETE FROM workspace_invites WHERE id = $1 AND user_id = $2")
+        .bind(path.into_inner())
+        .bind(&claims.sub)
+        .execute(pool.get_ref())
+        .await;
+    match declined {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({"declined": true})),
+        Err(e) => {
+            eprintln!("decline_invite error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ─── DELETE /api/users/{id}/membership ───
+
+// Takes a user out of the active workspace and its projects. Their
+// account, and what they tracked here, stays.
+pub async fn remove_member(pool: web::Data<DbPool>, req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let (Some(claims), Some(grants)) = (caller(&req), rbac::grants(&req)) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let user_id = path.into_inner();
+    let db = pool.get_ref();
+
+    if user_id == claims.sub {
+        return HttpResponse::BadRequest().body("Cannot remove yourself from the workspace");
+    }
+    let role = match rbac::target(db, &grants, &user_id, false).await {
+        Ok(role) => role,
+        Err(resp) => return resp,
+    };
+
+    // Along with their place in the workspace's projects and the tasks
+    // assigned to them there
+    let removed: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        query("DELETE FROM project_members WHERE user_id = $2 AND project_id IN (SELECT id FROM projects WHERE workspace_id = $1)")
+            .bind(&grants.workspace_id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+        query("UPDATE tasks SET assignee_id = NULL WHERE workspace_id = $1 AND assignee_id = $2")
+            .bind(&grants.workspace_id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+        query("DELETE FROM workspace_members WHERE workspace_id = $1 AND user_id = $2")
+            .bind(&grants.workspace_id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    match removed {
+        Ok(_) => {
+            let before = serde_json::json!({"role": role});
+            audit::log(db, &Actor::of(&req), "workspace.member_remove", "user", &user_id, Some(before), None).await;
+            HttpResponse::Ok().json(serde_json::json!({"removed": true}))
+        }
+        Err(e) => {
+            eprintln!("remove_member error: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
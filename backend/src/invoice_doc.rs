@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+use crate::models::invoice::InvoiceDetail;
+use crate::tz;
+
+// Minor units to "1234.56 EUR"
+pub fn money(amount: i64, currency: &str) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{}.{:02} {}", sign, amount.abs() / 100, amount.abs() % 100, currency)
+}
+
+fn hours(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+// Dates are shown in the zone the invoice was created in
+fn local_date(detail: &InvoiceDetail, at: DateTime<Utc>) -> String {
+    let zone = tz::parse(&detail.invoice.timezone).unwrap_or(Tz::UTC);
+    at.with_timezone(&zone).format("%Y-%m-%d").to_string()
+}
+
+fn period(detail: &InvoiceDetail) -> String {
+    // period_end is exclusive
+    let inv = &detail.invoice;
+    format!(
+        "{} to {}",
+        local_date(detail, inv.period_start),
+        local_date(detail, inv.period_end - Duration::seconds(1))
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn html(detail: &InvoiceDetail) -> String {
+    let inv = &detail.invoice;
+    let rows: String = detail
+        .lines
+        .iter()
+        .map(|l| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                l.line_no,
+                escape(&l.description),
+                hours(l.minutes),
+                money(l.hourly_rate_cents, &inv.currency),
+                money(l.amount, &inv.currency),
+            )
+        })
+        .collect();
+    let notes = if inv.notes.is_empty() {
+        String::new()
+    } else {
+        format!("<p class=\"notes\">{}</p>\n", escape(&inv.notes))
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Invoice {number}</title>
+<style>
+body {{ font-family: Helvetica, Arial, sans-serif; margin: 40px; color: #1e293b; }}
+table {{ width: 100%; border-collapse: collapse; margin-top: 24px; }}
+th, td {{ padding: 6px 8px; border-bottom: 1px solid #e2e8f0; text-align: left; }}
+.num {{ text-align: right; }}
+.total td {{ font-weight: bold; border-bottom: none; }}
+.meta {{ color: #64748b; }}
+</style>
+</head>
+<body>
+<h1>Invoice {number}</h1>
+<p class="meta">Client: {client}<br>Period: {period}<br>Issued: {issued}<br>Status: {status}</p>
+<table>
+<tr><th>#</th><th>Description</th><th class="num">Hours</th><th class="num">Rate</th><th class="num">Amount</th></tr>
+{rows}<tr class="total"><td></td><td>Total</td><td class="num">{total_hours}</td><td></td><td class="num">{total}</td></tr>
+</table>
+{notes}</body>
+</html>
+"#,
+        number = escape(&inv.number),
+        client = escape(&inv.client_name),
+        period = period(detail),
+        issued = local_date(detail, inv.created_at),
+        status = inv.status,
+        rows = rows,
+        total_hours = hours(detail.lines.iter().map(|l| l.minutes).sum()),
+        total = money(inv.total, &inv.currency),
+        notes = notes,
+    )
+}
+
+// The built-in Helvetica only covers WinAnsi (Windows-1252); anything else
+// is replaced rather than embedding a font.
+fn win_ansi(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '–' => 0x96,
+            '—' => 0x97,
+            c if (c as u32) < 0x80 || (0xA0..0x100).contains(&(c as u32)) => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        format!("{}...", s.chars().take(max - 3).collect::<String>())
+    }
+}
+
+const PAGE_WIDTH: f32 = 595.0; // A4
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const LINE_HEIGHT: f32 = 16.0;
+const COLUMNS: [f32; 5] = [50.0, 75.0, 340.0, 395.0, 475.0];
+
+pub fn pdf(detail: &InvoiceDetail) -> Vec<u8> {
+    let inv = &detail.invoice;
+    let font = Name(b"F1");
+    let bold = Name(b"F2");
+
+    // Text lines as (font, size, x, text) per row, laid out top to bottom
+    type Cell = (Name<'static>, f32, f32, String);
+    let mut rows: Vec<Vec<Cell>> = vec![
+        vec![(bold, 18.0, MARGIN, format!("Invoice {}", inv.number))],
+        vec![],
+        vec![(font, 10.0, MARGIN, format!("Client: {}", inv.client_name))],
+        vec![(font, 10.0, MARGIN, format!("Period: {}", period(detail)))],
+        vec![(font, 10.0, MARGIN, format!("Issued: {}", local_date(detail, inv.created_at)))],
+        vec![(font, 10.0, MARGIN, format!("Status: {}", inv.status))],
+        vec![],
+    ];
+    let header = ["#", "Description", "Hours", "Rate", "Amount"];
+    rows.push(header.iter().zip(COLUMNS).map(|(h, x)| (bold, 10.0, x, h.to_string())).collect());
+    for l in &detail.lines {
+        let cells = [
+            l.line_no.to_string(),
+            truncate(&l.description, 45),
+            hours(l.minutes),
+            money(l.hourly_rate_cents, &inv.currency),
+            money(l.amount, &inv.currency),
+        ];
+        rows.push(cells.into_iter().zip(COLUMNS).map(|(c, x)| (font, 10.0, x, c)).collect());
+    }
+    rows.push(vec![]);
+    rows.push(vec![
+        (bold, 10.0, COLUMNS[1], "Total".to_string()),
+        (bold, 10.0, COLUMNS[2], hours(detail.lines.iter().map(|l| l.minutes).sum())),
+        (bold, 10.0, COLUMNS[4], money(inv.total, &inv.currency)),
+    ]);
+    if !inv.notes.is_empty() {
+        rows.push(vec![]);
+        for line in inv.notes.lines() {
+            rows.push(vec![(font, 10.0, MARGIN, truncate(line, 90))]);
+        }
+    }
+
+    let per_page = ((PAGE_HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT) as usize;
+    let pages: Vec<&[Vec<Cell>]> = rows.chunks(per_page).collect();
+
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(5 + 2 * i as i32)).collect();
+
+    let mut doc = Pdf::new();
+    doc.catalog(catalog_id).pages(tree_id);
+    doc.pages(tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    doc.type1_font(font_id).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+    doc.type1_font(bold_id).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    for (page_rows, page_id) in pages.iter().zip(&page_ids) {
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = doc.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(tree_id);
+        page.contents(content_id);
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        fonts.pair(font, font_id);
+        fonts.pair(bold, bold_id);
+        fonts.finish();
+        resources.finish();
+        page.finish();
+
+        let mut content = Content::new();
+        let mut y = PAGE_HEIGHT - MARGIN;
+        for row in page_rows.iter() {
+            for (f, size, x, text) in row {
+                let bytes = win_ansi(text);
+                content.begin_text();
+                content.set_font(*f, *size);
+                content.next_line(*x, y);
+                content.show(Str(&bytes));
+                content.end_text();
+            }
+            y -= LINE_HEIGHT;
+        }
+        doc.stream(content_id, &content.finish());
+    }
+
+    doc.finish()
+}
@@ -0,0 +1,102 @@
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::db::DbPool;
+use crate::models::settings::LockOverrideQuery;
+use crate::rbac;
+
+// Reason to bypass the period lock with, when the caller asked for it.
+// Takes period.reopen, and never happens silently.
+pub fn override_reason(req: &HttpRequest, query: &LockOverrideQuery) -> Result<Option<String>, HttpResponse> {
+    if !query.override_lock {
+        return Ok(None);
+    }
+    if !rbac::grants(req).is_some_and(|g| g.has(rbac::PERIOD_REOPEN)) {
+        return Err(rbac::forbidden(rbac::PERIOD_REOPEN));
+    }
+    match query.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+        Some(reason) => Ok(Some(reason.to_string())),
+        None => Err(HttpResponse::BadRequest().body("reason is required to override the period lock")),
+    }
+}
+
+// Refuses changes to entries of `user_id` in `workspace_id` starting at any
+// of `times` when that falls in an approved timesheet week or in the
+// workspace's closed period. With `override_reason` the closed period (not
+// an approved week) is let through; the bypassed lock date is returned so
+// the caller can log it.
+pub async fn check(
+    pool: &DbPool,
+    workspace_id: &str,
+    user_id: &str,
+    times: &[DateTime<Utc>],
+    override_reason: Option<&str>,
+) -> Result<Option<NaiveDate>, HttpResponse> {
+    let week: Result<Option<NaiveDate>, sqlx::Error> = sqlx::query_scalar(
+        "SELECT week_start FROM timesheets
+         WHERE user_id = $1 AND workspace_id = $3 AND status = 'approved'
+           AND EXISTS (SELECT 1 FROM UNNEST($2::timestamptz[]) AS at WHERE at >= period_start AND at < period_end)
+         LIMIT 1"
+    )
+    .bind(user_id)
+    .bind(times)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await;
+
+    match week {
+        Ok(None) => {}
+        Ok(Some(week)) => return Err(HttpResponse::Conflict().body(format!("Timesheet for the week of {} is approved and locked", week))),
+        Err(e) => {
+            eprintln!("lock check error: {:?}", e);
+            return Err(HttpResponse::InternalServerError().finish());
+        }
+    }
+
+    let closed: Result<Option<NaiveDate>, sqlx::Error> = sqlx::query_scalar(
+        "SELECT lock_date FROM workspace_settings
+         WHERE workspace_id = $2 AND EXISTS (SELECT 1 FROM UNNEST($1::timestamptz[]) AS at WHERE at < locked_until)"
+    )
+    .bind(times)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+    .map(Option::flatten);
+
+    match closed {
+        Ok(None) => Ok(None),
+        Ok(Some(date)) if override_reason.is_some() => Ok(Some(date)),
+        Ok(Some(date)) => Err(HttpResponse::Conflict().body(format!("Period is closed through {}", date))),
+        Err(e) => {
+            eprintln!("lock check error: {:?}", e);
+            Err(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+pub async fn log(
+    pool: &DbPool,
+    workspace_id: &str,
+    actor_id: &str,
+    action: &str,
+    lock_date: Option<NaiveDate>,
+    entry_id: Option<&str>,
+    reason: &str,
+) {
+    let result = sqlx::query(
+        "INSERT INTO period_lock_log (workspace_id, actor_id, action, lock_date, entry_id, reason, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, NOW())"
+    )
+    .bind(workspace_id)
+    .bind(actor_id)
+    .bind(action)
+    .bind(lock_date)
+    .bind(entry_id)
+    .bind(reason)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("period lock log error: {:?}", e);
+    }
+}
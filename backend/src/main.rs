@@ -1,17 +1,36 @@
+mod audit;
+mod billing;
 mod db;
 mod handlers;
+mod invoice_doc;
+mod lock;
 mod models;
 mod routes;
 mod middleware;
+mod pagination;
+mod rbac;
+mod rounding;
+mod session;
+mod throttle;
+mod tz;
+mod two_factor;
 
 use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("🚀 Sic Mundus Backend starting on http://localhost:8006");
     dotenvy::dotenv().ok(); // Load .env if exists (dev)
 
+    // `migrate ...` manages the schema and exits without serving HTTP
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        let pool = db::connect().await;
+        return db::migrate::run_cli(&pool, &args[1..]).await;
+    }
+
+    println!("🚀 Sic Mundus Backend starting on http://localhost:8006");
+
     let pool = db::init_pool().await;
     let db_data = web::Data::new(pool);
 
@@ -20,6 +39,7 @@ async fn main() -> std::io::Result<()> {
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header()
+            .expose_headers([pagination::NEXT_CURSOR_HEADER])
             .max_age(3600);
 
         App::new()
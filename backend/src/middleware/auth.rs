@@ -1,11 +1,17 @@
 use std::future::{ready, Ready};
+use std::rc::Rc;
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    HttpMessage, error::ErrorUnauthorized,
+    http::Method, web, HttpMessage, error::{ErrorForbidden, ErrorUnauthorized},
 };
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
-use crate::models::user::AuthClaims;
+use crate::db::DbPool;
+use crate::session::{self, Access};
+
+// All a user can reach while the workspace makes them set up 2FA
+fn allowed_before_2fa_setup(path: &str) -> bool {
+    path == "/api/me" || path == "/api/me/2fa" || path.starts_with("/api/me/2fa/")
+}
 
 // There are two steps in middleware processing.
 // 1. Middleware initialization, middleware factory gets called with
@@ -16,7 +22,7 @@ pub struct Auth;
 // Middleware factory is `Transform` trait
 impl<S, B> Transform<S, ServiceRequest> for Auth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -27,17 +33,17 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddleware { service }))
+        ready(Ok(AuthMiddleware { service: Rc::new(service) }))
     }
 }
 
 pub struct AuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -49,35 +55,78 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         // Init validation
-        let auth_header = req.headers().get("Authorization");
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(String::from);
+        let Some(token) = token else {
+            return Box::pin(async { Err(ErrorUnauthorized("Missing or invalid Authorization header")) });
+        };
+
+        // Personal access tokens are opaque and looked up below; anything
+        // else has to be a JWT
+        let jwt_claims = if token.starts_with(session::PAT_PREFIX) {
+            None
+        } else {
+            match session::decode_access_token(&token, false) {
+                Some(claims) => Some(claims),
+                None => return Box::pin(async { Err(ErrorUnauthorized("Invalid token")) }),
+            }
+        };
 
-        if let Some(auth_header) = auth_header {
-            if let Ok(auth_str) = auth_header.to_str() {
-                if auth_str.starts_with("Bearer ") {
-                    let token = &auth_str[7..];
-                    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                    
-                    let decoding_key = DecodingKey::from_secret(secret.as_bytes());
-                    let validation = Validation::new(Algorithm::HS256);
+        // The token's workspace, unless the request names another
+        let requested_workspace = req
+            .headers()
+            .get(session::WORKSPACE_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .filter(|w| !w.is_empty())
+            .map(String::from);
 
-                    match decode::<AuthClaims>(token, &decoding_key, &validation) {
-                        Ok(token_data) => {
-                            // Attach claims to request extensions
-                            req.extensions_mut().insert(token_data.claims);
-                            let fut = self.service.call(req);
-                            return Box::pin(async move {
-                                let res = fut.await?;
-                                Ok(res)
-                            });
-                        }
-                        Err(_) => {
-                            return Box::pin(async { Err(ErrorUnauthorized("Invalid token")) });
-                        }
+        let service = self.service.clone();
+        Box::pin(async move {
+            let Some(pool) = req.app_data::<web::Data<DbPool>>().cloned() else {
+                return Err(ErrorUnauthorized("Invalid token"));
+            };
+
+            let mut claims = match jwt_claims {
+                // A valid signature is not enough: the session may have been
+                // revoked, or the user deleted since
+                Some(claims) => {
+                    if !session::check_access(pool.get_ref(), &claims).await {
+                        return Err(ErrorUnauthorized("Session expired or revoked"));
                     }
+                    claims
                 }
+                None => match session::authenticate_pat(pool.get_ref(), &token).await {
+                    Some(claims) => claims,
+                    None => return Err(ErrorUnauthorized("Invalid, expired or revoked token")),
+                },
+            };
+
+            // Membership and role are checked on every request; tokens
+            // without a workspace (personal access tokens) use the first one
+            let workspace_id = requested_workspace.clone().or_else(|| Some(claims.wid.clone()).filter(|w| !w.is_empty()));
+            let grants = match session::authorize(pool.get_ref(), &claims.sub, workspace_id.as_deref()).await {
+                Access::Granted(grants) => grants,
+                Access::TwoFactorSetupRequired(grants) if claims.scope.is_none() && allowed_before_2fa_setup(req.path()) => grants,
+                Access::TwoFactorSetupRequired(_) => return Err(ErrorForbidden("Two-factor authentication must be set up first")),
+                Access::Denied if requested_workspace.is_some() => return Err(ErrorForbidden("Not a member of this workspace")),
+                // Removed from the token's workspace: refreshing picks another
+                Access::Denied => return Err(ErrorUnauthorized("Session expired or revoked")),
+            };
+            claims.wid = grants.workspace_id.clone();
+            claims.role = grants.role.clone();
+
+            if claims.scope.as_deref() == Some("read") && !matches!(*req.method(), Method::GET | Method::HEAD) {
+                return Err(ErrorForbidden("Token is read-only"));
             }
-        }
 
-        Box::pin(async { Err(ErrorUnauthorized("Missing or invalid Authorization header")) })
+            // Attach claims and permissions to request extensions
+            req.extensions_mut().insert(claims);
+            req.extensions_mut().insert(grants);
+            service.call(req).await
+        })
     }
 }
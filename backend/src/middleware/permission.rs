@@ -0,0 +1,62 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::ErrorForbidden, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use crate::rbac::Grants;
+
+// Route guard: the caller's role must hold the permission. Runs inside
+// `Auth`, which attaches the caller's grants.
+pub struct Require(&'static str);
+
+pub fn require(permission: &'static str) -> Require {
+    Require(permission)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Require
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = RequireMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireMiddleware { service: Rc::new(service), permission: self.0 }))
+    }
+}
+
+pub struct RequireMiddleware<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let allowed = req.extensions().get::<Grants>().is_some_and(|g| g.has(self.permission));
+        if !allowed {
+            let message = format!("Missing permission: {}", self.permission);
+            return Box::pin(async move { Err(ErrorForbidden(message)) });
+        }
+
+        let service = self.service.clone();
+        Box::pin(async move { service.call(req).await })
+    }
+}
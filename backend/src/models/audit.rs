@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub actor_id: Option<String>,
+    pub actor_username: Option<String>, // gone once the actor is deleted
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query string for `GET /api/admin/audit`; list filters are comma-separated.
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>, // inclusive
+    pub to: Option<DateTime<Utc>>,   // exclusive
+    pub limit: Option<i64>,          // 100 by default
+    pub cursor: Option<String>,
+}
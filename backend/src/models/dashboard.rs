@@ -1,8 +1,11 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DashboardSummary {
+    pub timezone: String,
+    pub currency: String,
     pub total_tasks: i64,
     pub completed_tasks: i64,
     pub pending_tasks: i64,
@@ -22,7 +25,7 @@ pub struct RecentEntry {
     pub task_title: String,
     pub duration_minutes: i64,
     pub notes: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -31,10 +34,17 @@ pub struct ProjectStat {
     pub color: String,
     pub task_count: i64,
     pub total_minutes: i64,
+    pub billable_minutes: i64,
+    pub amount: i64, // minor units of the workspace currency
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct DailyMinutes {
-    pub date: Option<String>,
-    pub minutes: Option<i64>,
+    pub date: NaiveDate,
+    pub minutes: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DashboardQuery {
+    pub tz: Option<String>, // IANA name, defaults to the user's timezone
 }
@@ -1,22 +1,125 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct TimeEntry {
     pub id: String,
     pub task_id: String,
-    pub start_time: String,
-    pub end_time: Option<String>,
-    pub duration_minutes: i64,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub task_title: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_minutes: i64, // rounded from duration_seconds, for older clients
+    pub duration_seconds: i64,
     pub notes: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     pub user_id: String,
+    pub billable: bool,
+    pub invoice_id: Option<String>, // set once billed; the entry is then read-only
+    pub raw_seconds: Option<i64>, // timer measurement before rounding
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateTimeEntryRequest {
-    pub start_time: String,
-    pub end_time: Option<String>,
-    pub duration_minutes: i64,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    // Duration: duration_seconds, else duration_minutes, else the span
+    #[serde(default)]
+    pub duration_minutes: Option<i64>,
     #[serde(default)]
+    pub duration_seconds: Option<i64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub billable: Option<bool>, // defaults to the project's flag
+    #[serde(default)]
+    pub allow_overlap: bool, // store even if it overlaps other time
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTimeEntryRequest {
+    pub task_id: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<i64>,
+    pub duration_seconds: Option<i64>,
     pub notes: Option<String>,
+    pub billable: Option<bool>,
+    #[serde(default)]
+    pub allow_overlap: bool,
+}
+
+/// Query string for `GET /api/entries`. `from`/`to` take an RFC3339
+/// timestamp or a `YYYY-MM-DD` day in the user's timezone (`to` inclusive).
+#[derive(Debug, Deserialize)]
+pub struct EntryListQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub project_id: Option<String>,
+    pub task_id: Option<String>,
+    pub category: Option<String>,
+    pub tag_id: Option<String>,
+    pub billable: Option<bool>,
+    pub group_by: Option<String>, // day | week | task | project | tag
+    pub tz: Option<String>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+/// One bucket of `GET /api/entries?group_by=...`. `key` is the local date
+/// (week buckets start on Monday), the task, project or tag id. An entry
+/// whose task has several tags counts towards each of them.
+#[derive(Debug, Serialize, FromRow)]
+pub struct EntryGroupTotal {
+    pub key: Option<String>,
+    pub label: Option<String>,
+    pub total_minutes: i64,
+    pub entry_count: i64,
+}
+
+/// Existing time an entry would overlap: another entry or the running timer
+/// (which extends to now).
+#[derive(Debug, Serialize, FromRow)]
+pub struct EntryConflict {
+    pub kind: String, // entry | timer
+    pub id: String,
+    pub task_id: String,
+    pub task_title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Body of a rejected create/update. `code` is one of `end_before_start`,
+/// `negative_duration`, `duration_mismatch` or `overlap`.
+#[derive(Debug, Serialize)]
+pub struct EntryValidationError {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<EntryConflict>,
+}
+
+/// A pair of overlapping entries from `GET /api/entries/overlaps`.
+#[derive(Debug, Serialize, FromRow)]
+pub struct EntryOverlap {
+    pub entry_id: String,
+    pub task_id: String,
+    pub task_title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub other_entry_id: String,
+    pub other_task_id: String,
+    pub other_task_title: String,
+    pub other_start_time: DateTime<Utc>,
+    pub other_end_time: DateTime<Utc>,
+    pub overlap_seconds: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverlapQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub tz: Option<String>,
 }
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Money fields are minor units of `currency`.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Invoice {
+    pub id: String,
+    pub number: String,
+    pub user_id: String,
+    pub project_id: Option<String>,
+    pub client_name: String,
+    pub status: String, // draft | sent | paid
+    pub currency: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>, // exclusive
+    pub timezone: String,
+    pub total: i64,
+    pub notes: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One line per task (and rate, if entries on the task resolved to
+/// different rates).
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct InvoiceLine {
+    pub line_no: i32,
+    pub task_id: Option<String>,
+    pub description: String,
+    pub minutes: i64,
+    pub hourly_rate_cents: i64,
+    pub amount: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceDetail {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub lines: Vec<InvoiceLine>,
+}
+
+/// `from`/`to` take an RFC3339 timestamp or a `YYYY-MM-DD` day in the
+/// user's timezone (`to` inclusive), like `GET /api/entries`.
+#[derive(Debug, Deserialize)]
+pub struct CreateInvoiceRequest {
+    pub project_id: String,
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub client_name: Option<String>, // defaults to the project name
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tz: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateInvoiceStatusRequest {
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceListQuery {
+    pub status: Option<String>,     // comma-separated
+    pub project_id: Option<String>, // comma-separated
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceDocumentQuery {
+    pub format: Option<String>, // html (default) | pdf
+}
@@ -5,3 +5,26 @@ pub mod timer;
 pub mod subtask;
 pub mod project;
 pub mod user;
+pub mod tag;
+pub mod settings;
+pub mod invoice;
+pub mod timesheet;
+pub mod session;
+pub mod token;
+pub mod two_factor;
+pub mod throttle;
+pub mod role;
+
+use serde::{Deserialize, Deserializer};
+
+// For nullable fields in update requests: a missing field stays `None`, an
+// explicit `null` becomes `Some(None)` (clear the value).
+pub fn double_option<'de, D, T>(de: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(de).map(Some)
+}
+pub mod audit;
+pub mod workspace;
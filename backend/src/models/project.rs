@@ -1,14 +1,23 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use super::double_option;
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Project {
     pub id: String,
     pub name: String,
     pub color: String,
     pub description: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     pub user_id: String,
+    pub billable: bool,
+    pub hourly_rate_cents: Option<i64>,
+    // Timer rounding overrides; None inherits the workspace setting
+    pub rounding_mode: Option<String>,
+    pub rounding_step_minutes: Option<i32>,
+    pub rounding_min_seconds: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
@@ -17,7 +26,13 @@ pub struct ProjectWithStats {
     pub name: String,
     pub color: String,
     pub description: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
+    pub billable: bool,
+    pub hourly_rate_cents: Option<i64>,
+    pub rounding_mode: Option<String>,
+    pub rounding_step_minutes: Option<i32>,
+    pub rounding_min_seconds: Option<i32>,
+    pub role: String, // the caller's: owner | editor | viewer
     #[serde(default)]
     #[sqlx(default)]
     pub task_count: i64,
@@ -42,6 +57,16 @@ pub struct CreateProjectRequest {
     pub color: Option<String>,
     #[serde(default)]
     pub description: Option<String>,
+    #[serde(default)]
+    pub billable: Option<bool>,
+    #[serde(default)]
+    pub hourly_rate_cents: Option<i64>,
+    #[serde(default)]
+    pub rounding_mode: Option<String>,
+    #[serde(default)]
+    pub rounding_step_minutes: Option<i32>,
+    #[serde(default)]
+    pub rounding_min_seconds: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,4 +74,35 @@ pub struct UpdateProjectRequest {
     pub name: Option<String>,
     pub color: Option<String>,
     pub description: Option<String>,
+    pub billable: Option<bool>,
+    // null clears the rate so the user/workspace rate applies again
+    #[serde(default, deserialize_with = "double_option")]
+    pub hourly_rate_cents: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub rounding_mode: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub rounding_step_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub rounding_min_seconds: Option<Option<i32>>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProjectMember {
+    pub user_id: String,
+    pub username: String,
+    pub full_name: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddProjectMemberRequest {
+    pub username: String,
+    #[serde(default)]
+    pub role: Option<String>, // editor by default
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProjectMemberRequest {
+    pub role: String,
 }
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Role {
+    pub name: String,
+    pub description: String,
+    pub level: i32, // users manage users of lower-level roles
+    pub builtin: bool,
+    pub permissions: Vec<String>,
+    pub user_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoleRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub level: i32, // below the caller's own
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditRoleRequest {
+    pub description: Option<String>,
+    pub level: Option<i32>,
+    pub permissions: Option<Vec<String>>, // replaces the whole set
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::double_option;
+
+/// Workspace-wide settings. Rates are in minor units of `currency`.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct WorkspaceSettings {
+    pub currency: String,
+    pub default_hourly_rate_cents: Option<i64>,
+    pub rounding_mode: String, // exact | nearest | up, for timer entries
+    pub rounding_step_minutes: i32,
+    pub rounding_min_seconds: i32,
+    pub lock_date: Option<NaiveDate>, // entries up to and including this day are closed
+    pub lock_timezone: String,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub require_2fa_for_admins: bool, // roles with more than self-service permissions must set up 2FA
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsRequest {
+    pub currency: Option<String>, // ISO 4217 code, e.g. "EUR"
+    #[serde(default, deserialize_with = "double_option")]
+    pub default_hourly_rate_cents: Option<Option<i64>>,
+    pub rounding_mode: Option<String>,
+    pub rounding_step_minutes: Option<i32>,
+    pub rounding_min_seconds: Option<i32>,
+    pub require_2fa_for_admins: Option<bool>,
+}
+
+/// `lock_date` must be present; `null` reopens everything. Moving it earlier
+/// or clearing it takes period.reopen.
+#[derive(Debug, Deserialize)]
+pub struct UpdateLockRequest {
+    #[serde(default, deserialize_with = "double_option")]
+    pub lock_date: Option<Option<NaiveDate>>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub tz: Option<String>, // zone lock_date is read in, the caller's by default
+}
+
+/// `?override_lock=true&reason=...` lets holders of period.reopen write into
+/// a closed period; every use is logged.
+#[derive(Debug, Deserialize)]
+pub struct LockOverrideQuery {
+    #[serde(default)]
+    pub override_lock: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PeriodLockEvent {
+    pub id: i64,
+    pub actor_id: Option<String>,
+    pub actor_username: Option<String>,
+    pub action: String,
+    pub lock_date: Option<NaiveDate>,
+    pub entry_id: Option<String>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
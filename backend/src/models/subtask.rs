@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -8,7 +9,7 @@ pub struct Subtask {
     pub title: String,
     pub completed: bool,
     pub position: i32,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     pub user_id: String,
 }
 
@@ -1,6 +1,11 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
 
+use super::double_option;
+use super::tag::TaskTag;
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Task {
     pub id: String,
@@ -11,11 +16,17 @@ pub struct Task {
     #[serde(default = "default_priority")]
     #[sqlx(default)]
     pub priority: String,
-    pub user_id: String,
-    pub start_date: Option<String>,
-    pub due_date: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub user_id: String, // creator
+    #[serde(default)]
+    #[sqlx(default)]
+    pub assignee_id: Option<String>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub assignee_username: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     #[serde(default)]
     #[sqlx(default)]
     pub project_id: Option<String>,
@@ -37,6 +48,9 @@ pub struct Task {
     #[serde(default)]
     #[sqlx(default)]
     pub subtask_done: i64,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tags: Json<Vec<TaskTag>>,
 }
 
 fn default_priority() -> String {
@@ -57,10 +71,16 @@ pub struct AdminTask {
     pub user_id: String,
     pub username: String,
     pub full_name: String,
-    pub start_date: Option<String>,
-    pub due_date: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub assignee_id: Option<String>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub assignee_username: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     #[serde(default)]
     #[sqlx(default)]
     pub project_id: Option<String>,
@@ -79,6 +99,9 @@ pub struct AdminTask {
     #[serde(default)]
     #[sqlx(default)]
     pub subtask_done: i64,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tags: Json<Vec<TaskTag>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -96,6 +119,10 @@ pub struct CreateTaskRequest {
     pub start_date: Option<String>,
     #[serde(default)]
     pub due_date: Option<String>,
+    #[serde(default)]
+    pub tag_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub assignee_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -108,4 +135,29 @@ pub struct UpdateTaskRequest {
     pub project_id: Option<String>,
     pub start_date: Option<String>,
     pub due_date: Option<String>,
+    pub tag_ids: Option<Vec<String>>, // replaces the task's tags when present
+    // null unassigns the task
+    #[serde(default, deserialize_with = "double_option")]
+    pub assignee_id: Option<Option<String>>,
+}
+
+/// Query string for `GET /api/tasks` and `GET /api/admin/tasks`.
+/// List filters accept comma-separated values, e.g. `status=pending,in_progress`.
+#[derive(Debug, Deserialize)]
+pub struct TaskListQuery {
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub project_id: Option<String>,
+    pub category: Option<String>,
+    pub tag_id: Option<String>,
+    pub due_from: Option<NaiveDate>,
+    pub due_to: Option<NaiveDate>,
+    pub q: Option<String>, // matched against title and description
+    pub sort: Option<String>, // updated_at (default), created_at, due_date, title
+    pub order: Option<String>, // asc | desc (default)
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub assigned_to_me: Option<bool>, // own listing only
+    pub created_by_me: Option<bool>, // own listing only
+    pub user_id: Option<String>, // admin listing only
 }
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct LoginEvent {
+    pub id: i64,
+    pub event: String, // login_failed | two_factor_failed | account_locked | ip_locked | unlocked | registered
+    pub username: Option<String>,
+    pub user_id: Option<String>,
+    pub ip: Option<String>,
+    pub actor_id: Option<String>, // admin, for unlocked
+    pub actor_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query string for `GET /api/admin/login-events`; filters are comma-separated.
+#[derive(Debug, Deserialize)]
+pub struct LoginEventQuery {
+    pub event: Option<String>,
+    pub user_id: Option<String>,
+    pub ip: Option<String>,
+    pub limit: Option<i64>, // 100 by default
+}
+
+/// An account (by username) or address that is locked right now.
+#[derive(Debug, Serialize, FromRow)]
+pub struct Lockout {
+    pub scope: String, // account | ip
+    pub key: String,   // username or IP
+    pub user_id: Option<String>,
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: DateTime<Utc>,
+}
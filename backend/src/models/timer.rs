@@ -1,14 +1,30 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct ActiveTimer {
     pub id: String,
     pub task_id: String,
     pub task_title: String,
-    pub start_time: String,
+    pub start_time: DateTime<Utc>,
     pub notes: String,
-    pub elapsed_seconds: i64,
-    pub created_at: String,
+    #[sqlx(default)]
+    pub elapsed_seconds: i64, // excludes paused time
+    pub created_at: DateTime<Utc>,
+    pub paused_at: Option<DateTime<Utc>>, // set while paused
+    pub paused_seconds: i64,
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub workspace_id: String, // the task's, for lock checks
+}
+
+impl ActiveTimer {
+    // Running time as of `now`, without the finished pauses and the open one
+    pub fn elapsed_at(&self, now: DateTime<Utc>) -> i64 {
+        let until = self.paused_at.unwrap_or(now);
+        ((until - self.start_time).num_seconds() - self.paused_seconds).max(0)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,66 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A submitted week. Entries starting in `period_start..period_end` are
+/// locked while the timesheet is approved.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Timesheet {
+    pub id: String,
+    pub workspace_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub week_start: NaiveDate,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>, // exclusive
+    pub timezone: String,
+    pub status: String, // submitted | approved | rejected | reopened
+    pub comment: String,
+    pub submitted_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub total_minutes: i64,
+    pub entry_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct TimesheetEvent {
+    pub id: i64,
+    pub actor_id: Option<String>,
+    pub actor_username: Option<String>, // None once the actor was deleted
+    pub action: String,
+    pub status: String,
+    pub comment: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimesheetDetail {
+    #[serde(flatten)]
+    pub timesheet: Timesheet,
+    pub history: Vec<TimesheetEvent>,
+}
+
+/// `week` is any day of the week in the user's timezone, the current week
+/// when omitted.
+#[derive(Debug, Deserialize)]
+pub struct SubmitTimesheetRequest {
+    #[serde(default)]
+    pub week: Option<NaiveDate>,
+    #[serde(default)]
+    pub tz: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimesheetDecisionRequest {
+    #[serde(default)]
+    pub comment: Option<String>, // required to reject
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimesheetListQuery {
+    pub status: Option<String>,  // comma-separated
+    pub user_id: Option<String>, // comma-separated, admin list only
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PersonalAccessToken {
+    pub id: String,
+    pub name: String,
+    pub token_prefix: String, // first characters, to recognise it
+    pub scope: String,        // read | write
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Returned once on creation; the token can't be shown again.
+#[derive(Debug, Serialize)]
+pub struct CreatedToken {
+    #[serde(flatten)]
+    pub info: PersonalAccessToken,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scope: Option<String>, // read (default) | write
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>, // never when omitted
+}
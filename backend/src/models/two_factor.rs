@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub required: bool, // by the workspace policy, for the user's role
+    pub recovery_codes_remaining: i64,
+}
+
+/// Not active until confirmed with a code from the authenticator app.
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetup {
+    pub secret: String, // base32, for manual entry
+    pub otpauth_url: String,
+}
+
+/// Shown once; each code signs in a single time instead of a TOTP code.
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    pub code: String, // TOTP or recovery code
+}
+
+/// Login answer for users with 2FA, in place of the tokens.
+#[derive(Debug, Serialize)]
+pub struct LoginChallenge {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_in: i64, // seconds
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyLoginRequest {
+    pub challenge_token: String,
+    pub code: String, // TOTP or recovery code
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use sqlx::FromRow;
@@ -7,12 +8,18 @@ pub struct User {
     pub id: String,
     pub username: String,
     pub full_name: String,
-    pub role: String,
+    #[sqlx(default)]
+    pub role: String, // in the active workspace
     #[serde(skip_serializing)] // Never send password hash to client
     pub password_hash: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     #[sqlx(default)]
     pub force_change_password: bool,
+    pub timezone: String,
+    #[sqlx(default)]
+    pub hourly_rate_cents: Option<i64>, // in the active workspace
+    #[sqlx(default)]
+    pub totp_enabled: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,7 +31,10 @@ pub struct RegisterRequest {
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateProfileRequest {
-    pub full_name: String,
+    #[serde(default)]
+    pub full_name: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>, // IANA name, e.g. "Asia/Jakarta"
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,23 +43,59 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// `token` is the short-lived access token; `refresh_token` gets a new
+/// pair from `/api/auth/refresh` and is replaced every time.
 #[derive(Debug, Serialize)]
-pub struct AuthResponse {
+pub struct TokenPair {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // seconds until `token` expires
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthResponse {
+    #[serde(flatten)]
+    pub tokens: TokenPair,
     pub user: User,
+    pub workspace_id: String, // the session starts out in this one
+    // Set when the workspace requires 2FA for the user's role and it isn't
+    // enabled; until then only /api/me and /api/me/2fa are reachable
+    pub two_factor_setup_required: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AuthClaims {
     pub sub: String, // User ID
-    pub role: String,
+    #[serde(default)]
+    pub wid: String, // Active workspace ID
+    pub role: String, // Role in that workspace
+    pub sid: String, // Session ID, or token ID for personal access tokens
     pub exp: usize,
+    // Only set for personal access tokens: read | write
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    #[serde(default)]
+    pub refresh_token: Option<String>,
 }
 #[derive(Debug, Deserialize)]
 pub struct UpdateRoleRequest {
     pub role: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateRateRequest {
+    pub hourly_rate_cents: Option<i64>, // null falls back to the workspace default
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ResetPasswordRequest {
     pub new_password: String,
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A workspace the user belongs to, with their role in it.
+#[derive(Debug, Serialize, FromRow)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub current: bool, // the workspace the request runs in
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkspaceRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMemberRequest {
+    pub username: String,
+    pub role: String,
+}
+
+/// A pending invitation of the caller into another workspace.
+#[derive(Debug, Serialize, FromRow)]
+pub struct WorkspaceInvite {
+    pub id: String,
+    pub workspace_id: String,
+    pub workspace_name: String,
+    pub role: String,
+    pub invited_by: Option<String>, // username
+    pub created_at: DateTime<Utc>,
+}
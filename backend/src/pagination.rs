@@ -0,0 +1,65 @@
+use actix_web::HttpResponse;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+// List bodies stay plain JSON arrays; the token for the next page (if any)
+// is returned in this header and passed back as `?cursor=`.
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+pub const MAX_LIMIT: i64 = 500;
+
+// Keyset cursor: the ordering it was issued for plus the sort value and id
+// of the last row on the page. Stable under inserts, unlike OFFSET.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cursor {
+    pub order: String,
+    pub value: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+// Comma-separated list filter, e.g. `?status=pending,in_progress`
+pub fn csv(val: &Option<String>) -> Option<Vec<String>> {
+    let items: Vec<String> = val.as_deref()?
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if items.is_empty() { None } else { Some(items) }
+}
+
+pub fn parse_limit(limit: Option<i64>) -> Result<Option<i64>, HttpResponse> {
+    match limit {
+        Some(l) if !(1..=MAX_LIMIT).contains(&l) => Err(HttpResponse::BadRequest()
+            .body(format!("limit must be between 1 and {}", MAX_LIMIT))),
+        other => Ok(other),
+    }
+}
+
+// `rows` must have been fetched with `LIMIT limit + 1`; the extra row only
+// signals that another page exists.
+pub fn page_response<T: Serialize>(
+    mut rows: Vec<T>,
+    limit: Option<i64>,
+    cursor_for: impl Fn(&T) -> Cursor,
+) -> HttpResponse {
+    let mut resp = HttpResponse::Ok();
+    if let Some(limit) = limit {
+        if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            if let Some(last) = rows.last() {
+                resp.insert_header((NEXT_CURSOR_HEADER, cursor_for(last).encode()));
+            }
+        }
+    }
+    resp.json(rows)
+}
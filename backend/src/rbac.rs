@@ -0,0 +1,116 @@
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use sqlx::{query_as, query_scalar};
+
+use crate::db::DbPool;
+
+// ─── Permissions ───
+
+pub const ACCOUNT_SELF: &str = "account.self"; // own profile, password, sessions, tokens, 2FA
+pub const TIME_TRACK: &str = "time.track"; // own tasks, entries, timer, projects, tags, timesheets
+pub const INVOICES_MANAGE: &str = "invoices.manage";
+pub const USERS_VIEW: &str = "users.view";
+pub const USERS_MANAGE: &str = "users.manage"; // rates, password resets, sessions, 2FA resets, unlocks, deletion
+pub const USERS_ASSIGN_ROLES: &str = "users.assign_roles";
+pub const USERS_MANAGE_PEERS: &str = "users.manage_peers"; // the above on users of one's own level too
+pub const TASKS_ASSIGN: &str = "tasks.assign"; // assign tasks one didn't create
+pub const REPORTS_VIEW: &str = "reports.view";
+pub const TIMESHEETS_REVIEW: &str = "timesheets.review";
+pub const SECURITY_AUDIT: &str = "security.audit"; // audit log, lockouts, login events, lock log
+pub const PERIOD_LOCK: &str = "period.lock";
+pub const PERIOD_REOPEN: &str = "period.reopen"; // move the lock back, override it
+pub const SETTINGS_MANAGE: &str = "settings.manage";
+pub const ROLES_MANAGE: &str = "roles.manage";
+
+pub const ALL: [&str; 15] = [
+    ACCOUNT_SELF, TIME_TRACK, INVOICES_MANAGE, USERS_VIEW, USERS_MANAGE, USERS_ASSIGN_ROLES, USERS_MANAGE_PEERS,
+    TASKS_ASSIGN, REPORTS_VIEW, TIMESHEETS_REVIEW, SECURITY_AUDIT, PERIOD_LOCK, PERIOD_REOPEN, SETTINGS_MANAGE, ROLES_MANAGE,
+];
+
+// Self-service; a role with anything else counts as privileged, e.g. for
+// the 2FA policy
+pub const BASIC: [&str; 3] = [ACCOUNT_SELF, TIME_TRACK, INVOICES_MANAGE];
+
+/// What the caller's role in the active workspace allows, loaded with the
+/// session on every request.
+#[derive(Debug, Clone)]
+pub struct Grants {
+    pub workspace_id: String,
+    pub role: String,
+    pub level: i32,
+    pub permissions: Vec<String>,
+}
+
+impl Grants {
+    pub fn has(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    pub fn privileged(&self) -> bool {
+        self.permissions.iter().any(|p| !BASIC.contains(&p.as_str()))
+    }
+
+    // Whether users whose role is at `level` are within reach. `peers`
+    // allows the caller's own level given users.manage_peers; deleting
+    // users and changing roles never does.
+    pub fn reaches(&self, level: i32, peers: bool) -> bool {
+        level < self.level || (peers && level == self.level && self.has(USERS_MANAGE_PEERS))
+    }
+}
+
+pub fn grants(req: &HttpRequest) -> Option<Grants> {
+    req.extensions().get::<Grants>().cloned()
+}
+
+pub fn forbidden(permission: &str) -> HttpResponse {
+    HttpResponse::Forbidden().body(format!("Missing permission: {}", permission))
+}
+
+// Role of `user_id` in the caller's workspace if the caller may manage
+// them: 404 for users who aren't members, 403 for those out of reach
+pub async fn target(pool: &DbPool, grants: &Grants, user_id: &str, peers: bool) -> Result<String, HttpResponse> {
+    let target: Option<(String, i32)> = query_as(
+        "SELECT m.role, r.level FROM workspace_members m JOIN roles r ON r.name = m.role AND (r.workspace_id IS NULL OR r.workspace_id = m.workspace_id)
+         WHERE m.workspace_id = $1 AND m.user_id = $2"
+    )
+    .bind(&grants.workspace_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    match target {
+        None => Err(HttpResponse::NotFound().finish()),
+        Some((role, level)) if grants.reaches(level, peers) => Ok(role),
+        Some((role, _)) => Err(HttpResponse::Forbidden().body(format!("Cannot manage users with role {}", role))),
+    }
+}
+
+// Like `target`, for changes to the account itself (password, 2FA,
+// sessions, deletion). Those reach into every workspace the user is in, so
+// only users who belong to the caller's workspace alone qualify.
+pub async fn account(pool: &DbPool, grants: &Grants, user_id: &str, peers: bool) -> Result<String, HttpResponse> {
+    let role = target(pool, grants, user_id, peers).await?;
+    let elsewhere: bool = query_scalar("SELECT EXISTS (SELECT 1 FROM workspace_members WHERE user_id = $1 AND workspace_id <> $2)")
+        .bind(user_id)
+        .bind(&grants.workspace_id)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(true);
+    if elsewhere {
+        return Err(HttpResponse::Forbidden().body("User also belongs to other workspaces"));
+    }
+    Ok(role)
+}
+
+// Members of the caller's workspace the caller may manage, for list filters
+pub async fn reachable_users(pool: &DbPool, grants: &Grants) -> Result<Vec<String>, sqlx::Error> {
+    query_scalar(
+        "SELECT m.user_id FROM workspace_members m JOIN roles r ON r.name = m.role AND (r.workspace_id IS NULL OR r.workspace_id = m.workspace_id)
+         WHERE m.workspace_id = $1 AND (r.level < $2 OR (r.level = $2 AND $3))"
+    )
+    .bind(&grants.workspace_id)
+    .bind(grants.level)
+    .bind(grants.has(USERS_MANAGE_PEERS))
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+
+pub const ROUNDING_MODES: [&str; 3] = ["exact", "nearest", "up"];
+
+// How a timer run becomes an entry: `exact` keeps the measured seconds,
+// `nearest`/`up` round to a multiple of `step_minutes`. Runs shorter than
+// `min_seconds` are dropped.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RoundingPolicy {
+    pub mode: String,
+    pub step_minutes: i32,
+    pub min_seconds: i32,
+}
+
+impl Default for RoundingPolicy {
+    // Matches the historical behaviour: always round up to a whole minute
+    fn default() -> Self {
+        RoundingPolicy { mode: "up".into(), step_minutes: 1, min_seconds: 0 }
+    }
+}
+
+impl RoundingPolicy {
+    // Seconds to record for a run of `secs`, or None when it is discarded
+    pub fn apply(&self, secs: i64) -> Option<i64> {
+        if secs < self.min_seconds as i64 {
+            return None;
+        }
+        let step = self.step_minutes.max(1) as i64 * 60;
+        Some(match self.mode.as_str() {
+            "nearest" => (secs + step / 2) / step * step,
+            "up" => (secs + step - 1) / step * step,
+            _ => secs,
+        })
+    }
+}
+
+// Whole minutes reported in `duration_minutes`, rounded to the nearest
+pub fn to_minutes(secs: i64) -> i64 {
+    (secs + 30) / 60
+}
+
+// Effective policy for entries on `task_id`: each project field overrides
+// the one of the task's workspace
+pub async fn policy_for_task(pool: &DbPool, task_id: &str) -> RoundingPolicy {
+    sqlx::query_as::<_, RoundingPolicy>(
+        "SELECT COALESCE(p.rounding_mode, ws.rounding_mode) AS mode,
+                COALESCE(p.rounding_step_minutes, ws.rounding_step_minutes) AS step_minutes,
+                COALESCE(p.rounding_min_seconds, ws.rounding_min_seconds) AS min_seconds
+         FROM tasks t
+         JOIN workspace_settings ws ON ws.workspace_id = t.workspace_id
+         LEFT JOIN projects p ON p.id = t.project_id
+         WHERE t.id = $1"
+    )
+    .bind(task_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None)
+    .unwrap_or_default()
+}
+
+pub fn validate(mode: Option<&str>, step_minutes: Option<i32>, min_seconds: Option<i32>) -> Result<(), String> {
+    if let Some(m) = mode {
+        if !ROUNDING_MODES.contains(&m) {
+            return Err(format!("rounding_mode must be one of {}", ROUNDING_MODES.join(", ")));
+        }
+    }
+    if step_minutes.is_some_and(|s| s < 1) {
+        return Err("rounding_step_minutes must be at least 1".into());
+    }
+    if min_seconds.is_some_and(|s| s < 0) {
+        return Err("rounding_min_seconds must not be negative".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: &str, step_minutes: i32, min_seconds: i32) -> RoundingPolicy {
+        RoundingPolicy { mode: mode.into(), step_minutes, min_seconds }
+    }
+
+    #[test]
+    fn default_rounds_up_to_the_minute() {
+        let p = RoundingPolicy::default();
+        assert_eq!(p.apply(0), Some(0));
+        assert_eq!(p.apply(1), Some(60));
+        assert_eq!(p.apply(60), Some(60));
+        assert_eq!(p.apply(61), Some(120));
+    }
+
+    #[test]
+    fn exact_keeps_seconds() {
+        assert_eq!(policy("exact", 15, 0).apply(437), Some(437));
+    }
+
+    #[test]
+    fn nearest_rounds_half_steps_up() {
+        let p = policy("nearest", 15, 0);
+        assert_eq!(p.apply(7 * 60 + 29), Some(0));
+        assert_eq!(p.apply(7 * 60 + 30), Some(15 * 60));
+        assert_eq!(p.apply(22 * 60 + 29), Some(15 * 60));
+        assert_eq!(p.apply(22 * 60 + 30), Some(30 * 60));
+    }
+
+    #[test]
+    fn up_rounds_to_the_next_step() {
+        let p = policy("up", 6, 0);
+        assert_eq!(p.apply(6 * 60), Some(6 * 60));
+        assert_eq!(p.apply(6 * 60 + 1), Some(12 * 60));
+    }
+
+    #[test]
+    fn runs_under_the_threshold_are_discarded() {
+        let p = policy("up", 15, 120);
+        assert_eq!(p.apply(119), None);
+        assert_eq!(p.apply(120), Some(15 * 60));
+    }
+
+    #[test]
+    fn step_below_one_minute_counts_as_one() {
+        assert_eq!(policy("up", 0, 0).apply(61), Some(120));
+    }
+
+    #[test]
+    fn minutes_round_to_the_nearest() {
+        assert_eq!(to_minutes(29), 0);
+        assert_eq!(to_minutes(30), 1);
+        assert_eq!(to_minutes(89), 1);
+        assert_eq!(to_minutes(90), 2);
+    }
+
+    #[test]
+    fn validate_rejects_unknown_modes_and_bad_numbers() {
+        assert!(validate(Some("nearest"), Some(15), Some(0)).is_ok());
+        assert!(validate(Some("down"), None, None).is_err());
+        assert!(validate(None, Some(0), None).is_err());
+        assert!(validate(None, None, Some(-1)).is_err());
+    }
+}
@@ -1,54 +1,124 @@
 use actix_web::web;
 use crate::{handlers, middleware};
+use crate::middleware::permission::require;
+use crate::rbac::*;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     // Public Auth Routes
     cfg.service(
         web::scope("/api/auth")
             .route("/login", web::post().to(handlers::auth::login))
+            .route("/login/verify", web::post().to(handlers::auth::verify_login))
             .route("/register", web::post().to(handlers::auth::register))
+            .route("/refresh", web::post().to(handlers::auth::refresh))
+            .route("/logout", web::post().to(handlers::auth::logout))
     );
 
-    // Protected Routes
+    // Protected Routes; each one names the permission it needs
     cfg.service(
         web::scope("/api")
             .wrap(middleware::auth::Auth)
-            .route("/me", web::get().to(handlers::auth::me))
-            .route("/password", web::put().to(handlers::auth::change_password))
-            .route("/me/profile", web::put().to(handlers::user::update_profile))
+            .route("/me", web::get().to(handlers::auth::me).wrap(require(ACCOUNT_SELF)))
+            .route("/password", web::put().to(handlers::auth::change_password).wrap(require(ACCOUNT_SELF)))
+            .route("/me/profile", web::put().to(handlers::user::update_profile).wrap(require(ACCOUNT_SELF)))
+            .route("/me/sessions", web::get().to(handlers::session::get_my_sessions).wrap(require(ACCOUNT_SELF)))
+            .route("/me/sessions", web::delete().to(handlers::session::revoke_other_sessions).wrap(require(ACCOUNT_SELF)))
+            .route("/me/sessions/{id}", web::delete().to(handlers::session::revoke_my_session).wrap(require(ACCOUNT_SELF)))
+            .route("/me/tokens", web::get().to(handlers::token::get_tokens).wrap(require(ACCOUNT_SELF)))
+            .route("/me/tokens", web::post().to(handlers::token::create_token).wrap(require(ACCOUNT_SELF)))
+            .route("/me/tokens/{id}", web::delete().to(handlers::token::revoke_token).wrap(require(ACCOUNT_SELF)))
+            .route("/me/2fa", web::get().to(handlers::two_factor::get_status).wrap(require(ACCOUNT_SELF)))
+            .route("/me/2fa/setup", web::post().to(handlers::two_factor::setup).wrap(require(ACCOUNT_SELF)))
+            .route("/me/2fa/confirm", web::post().to(handlers::two_factor::confirm).wrap(require(ACCOUNT_SELF)))
+            .route("/me/2fa/recovery-codes", web::post().to(handlers::two_factor::regenerate_recovery_codes).wrap(require(ACCOUNT_SELF)))
+            .route("/me/2fa/disable", web::post().to(handlers::two_factor::disable).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces", web::get().to(handlers::workspace::get_workspaces).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces", web::post().to(handlers::workspace::create).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces/{id}/switch", web::post().to(handlers::workspace::switch).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces/invites", web::get().to(handlers::workspace::get_invites).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces/invites/{id}/accept", web::post().to(handlers::workspace::accept_invite).wrap(require(ACCOUNT_SELF)))
+            .route("/workspaces/invites/{id}", web::delete().to(handlers::workspace::decline_invite).wrap(require(ACCOUNT_SELF)))
             // Admin Routes
-            .route("/users", web::get().to(handlers::user::get_users))
-            .route("/users/{id}", web::delete().to(handlers::user::delete_user))
-            .route("/users/{id}/role", web::put().to(handlers::user::update_role))
-            .route("/users/{id}/password", web::put().to(handlers::user::reset_password))
-            .route("/admin/tasks", web::get().to(handlers::task::get_all_tasks_admin))
-            .route("/admin/time-report", web::get().to(handlers::user::get_time_report_admin))
+            .route("/users", web::get().to(handlers::user::get_users).wrap(require(USERS_VIEW)))
+            .route("/users", web::post().to(handlers::workspace::add_member).wrap(require(USERS_ASSIGN_ROLES)))
+            .route("/users/{id}", web::delete().to(handlers::user::delete_user).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/role", web::put().to(handlers::user::update_role).wrap(require(USERS_ASSIGN_ROLES)))
+            .route("/users/{id}/membership", web::delete().to(handlers::workspace::remove_member).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/rate", web::put().to(handlers::user::update_rate).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/password", web::put().to(handlers::user::reset_password).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/sessions", web::get().to(handlers::session::get_user_sessions).wrap(require(USERS_VIEW)))
+            .route("/users/{id}/sessions", web::delete().to(handlers::session::revoke_user_sessions).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/sessions/{session_id}", web::delete().to(handlers::session::revoke_user_session).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/2fa", web::delete().to(handlers::two_factor::reset_user_2fa).wrap(require(USERS_MANAGE)))
+            .route("/users/{id}/unlock", web::post().to(handlers::throttle::unlock_user).wrap(require(USERS_MANAGE)))
+            .route("/roles", web::get().to(handlers::role::get_roles).wrap(require(USERS_VIEW)))
+            .route("/roles", web::post().to(handlers::role::create_role).wrap(require(ROLES_MANAGE)))
+            .route("/roles/{name}", web::put().to(handlers::role::update_role).wrap(require(ROLES_MANAGE)))
+            .route("/roles/{name}", web::delete().to(handlers::role::delete_role).wrap(require(ROLES_MANAGE)))
+            .route("/permissions", web::get().to(handlers::role::get_permissions).wrap(require(ROLES_MANAGE)))
+            .route("/admin/tasks", web::get().to(handlers::task::get_all_tasks_admin).wrap(require(REPORTS_VIEW)))
+            .route("/admin/time-report", web::get().to(handlers::user::get_time_report_admin).wrap(require(REPORTS_VIEW)))
+            .route("/admin/timesheets", web::get().to(handlers::timesheet::get_timesheets_admin).wrap(require(TIMESHEETS_REVIEW)))
+            .route("/admin/lockouts", web::get().to(handlers::throttle::get_lockouts).wrap(require(SECURITY_AUDIT)))
+            .route("/admin/audit", web::get().to(handlers::audit::get_audit_events).wrap(require(SECURITY_AUDIT)))
+            .route("/admin/login-events", web::get().to(handlers::throttle::get_login_events).wrap(require(SECURITY_AUDIT)))
+            .route("/settings", web::get().to(handlers::settings::get_settings).wrap(require(ACCOUNT_SELF)))
+            .route("/settings", web::put().to(handlers::settings::update_settings).wrap(require(SETTINGS_MANAGE)))
+            .route("/settings/lock", web::put().to(handlers::settings::update_lock).wrap(require(PERIOD_LOCK)))
+            .route("/settings/lock/log", web::get().to(handlers::settings::get_lock_log).wrap(require(SECURITY_AUDIT)))
             // Task routes
-            .route("/tasks/bulk-delete", web::post().to(handlers::task::delete_tasks_bulk))
-            .route("/tasks", web::get().to(handlers::task::get_tasks))
-            .route("/tasks", web::post().to(handlers::task::create_task))
-            .route("/tasks/{id}", web::put().to(handlers::task::update_task))
-            .route("/tasks/{id}", web::delete().to(handlers::task::delete_task))
+            .route("/tasks/bulk-delete", web::post().to(handlers::task::delete_tasks_bulk).wrap(require(TIME_TRACK)))
+            .route("/tasks", web::get().to(handlers::task::get_tasks).wrap(require(TIME_TRACK)))
+            .route("/tasks", web::post().to(handlers::task::create_task).wrap(require(TIME_TRACK)))
+            .route("/tasks/{id}", web::put().to(handlers::task::update_task).wrap(require(TIME_TRACK)))
+            .route("/tasks/{id}", web::delete().to(handlers::task::delete_task).wrap(require(TIME_TRACK)))
             // Time entry routes
-            .route("/tasks/{id}/entries", web::get().to(handlers::entry::get_entries))
-            .route("/tasks/{id}/entries", web::post().to(handlers::entry::create_entry))
-            .route("/entries/{id}", web::delete().to(handlers::entry::delete_entry))
-            .route("/entries", web::get().to(handlers::entry::get_all_entries))
+            .route("/tasks/{id}/entries", web::get().to(handlers::entry::get_entries).wrap(require(TIME_TRACK)))
+            .route("/tasks/{id}/entries", web::post().to(handlers::entry::create_entry).wrap(require(TIME_TRACK)))
+            .route("/entries/overlaps", web::get().to(handlers::entry::get_entry_overlaps).wrap(require(TIME_TRACK)))
+            .route("/entries/{id}", web::put().to(handlers::entry::update_entry).wrap(require(TIME_TRACK)))
+            .route("/entries/{id}", web::delete().to(handlers::entry::delete_entry).wrap(require(TIME_TRACK)))
+            .route("/entries", web::get().to(handlers::entry::get_all_entries).wrap(require(TIME_TRACK)))
             // Subtask routes
-            .route("/tasks/{id}/subtasks", web::get().to(handlers::subtask::get_subtasks))
-            .route("/tasks/{id}/subtasks", web::post().to(handlers::subtask::create_subtask))
-            .route("/subtasks/{id}", web::put().to(handlers::subtask::update_subtask))
-            .route("/subtasks/{id}", web::delete().to(handlers::subtask::delete_subtask))
+            .route("/tasks/{id}/subtasks", web::get().to(handlers::subtask::get_subtasks).wrap(require(TIME_TRACK)))
+            .route("/tasks/{id}/subtasks", web::post().to(handlers::subtask::create_subtask).wrap(require(TIME_TRACK)))
+            .route("/subtasks/{id}", web::put().to(handlers::subtask::update_subtask).wrap(require(TIME_TRACK)))
+            .route("/subtasks/{id}", web::delete().to(handlers::subtask::delete_subtask).wrap(require(TIME_TRACK)))
             // Project routes
-            .route("/projects", web::get().to(handlers::project::get_projects))
-            .route("/projects", web::post().to(handlers::project::create_project))
-            .route("/projects/{id}", web::put().to(handlers::project::update_project))
-            .route("/projects/{id}", web::delete().to(handlers::project::delete_project))
+            .route("/projects", web::get().to(handlers::project::get_projects).wrap(require(TIME_TRACK)))
+            .route("/projects", web::post().to(handlers::project::create_project).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}", web::put().to(handlers::project::update_project).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}", web::delete().to(handlers::project::delete_project).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}/members", web::get().to(handlers::project::get_members).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}/members", web::post().to(handlers::project::add_member).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}/members/{user_id}", web::put().to(handlers::project::update_member).wrap(require(TIME_TRACK)))
+            .route("/projects/{id}/members/{user_id}", web::delete().to(handlers::project::remove_member).wrap(require(TIME_TRACK)))
+            // Tag routes
+            .route("/tags", web::get().to(handlers::tag::get_tags).wrap(require(TIME_TRACK)))
+            .route("/tags", web::post().to(handlers::tag::create_tag).wrap(require(TIME_TRACK)))
+            .route("/tags/{id}", web::put().to(handlers::tag::update_tag).wrap(require(TIME_TRACK)))
+            .route("/tags/{id}", web::delete().to(handlers::tag::delete_tag).wrap(require(TIME_TRACK)))
+            // Invoice routes
+            .route("/invoices", web::get().to(handlers::invoice::get_invoices).wrap(require(INVOICES_MANAGE)))
+            .route("/invoices", web::post().to(handlers::invoice::create_invoice).wrap(require(INVOICES_MANAGE)))
+            .route("/invoices/{id}", web::get().to(handlers::invoice::get_invoice).wrap(require(INVOICES_MANAGE)))
+            .route("/invoices/{id}", web::delete().to(handlers::invoice::delete_invoice).wrap(require(INVOICES_MANAGE)))
+            .route("/invoices/{id}/status", web::put().to(handlers::invoice::update_invoice_status).wrap(require(INVOICES_MANAGE)))
+            .route("/invoices/{id}/document", web::get().to(handlers::invoice::get_invoice_document).wrap(require(INVOICES_MANAGE)))
+            // Timesheet routes
+            .route("/timesheets", web::get().to(handlers::timesheet::get_timesheets).wrap(require(TIME_TRACK)))
+            .route("/timesheets", web::post().to(handlers::timesheet::submit_timesheet).wrap(require(TIME_TRACK)))
+            .route("/timesheets/{id}", web::get().to(handlers::timesheet::get_timesheet).wrap(require(TIME_TRACK)))
+            .route("/timesheets/{id}/approve", web::post().to(handlers::timesheet::approve_timesheet).wrap(require(TIMESHEETS_REVIEW)))
+            .route("/timesheets/{id}/reject", web::post().to(handlers::timesheet::reject_timesheet).wrap(require(TIMESHEETS_REVIEW)))
+            .route("/timesheets/{id}/reopen", web::post().to(handlers::timesheet::reopen_timesheet).wrap(require(TIMESHEETS_REVIEW)))
             // Dashboard
-            .route("/dashboard/summary", web::get().to(handlers::dashboard::get_dashboard))
+            .route("/dashboard/summary", web::get().to(handlers::dashboard::get_dashboard).wrap(require(TIME_TRACK)))
             // Timer
-            .route("/timer/start/{task_id}", web::post().to(handlers::timer::start_timer))
-            .route("/timer/stop", web::post().to(handlers::timer::stop_timer))
-            .route("/timer/active", web::get().to(handlers::timer::get_active_timer))
+            .route("/timer/start/{task_id}", web::post().to(handlers::timer::start_timer).wrap(require(TIME_TRACK)))
+            .route("/timer/stop", web::post().to(handlers::timer::stop_timer).wrap(require(TIME_TRACK)))
+            .route("/timer/pause", web::post().to(handlers::timer::pause_timer).wrap(require(TIME_TRACK)))
+            .route("/timer/resume", web::post().to(handlers::timer::resume_timer).wrap(require(TIME_TRACK)))
+            .route("/timer/active", web::get().to(handlers::timer::get_active_timer).wrap(require(TIME_TRACK)))
     );
 }
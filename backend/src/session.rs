@@ -0,0 +1,344 @@
+use actix_web::HttpRequest;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::query;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::models::session::SessionInfo;
+use crate::models::user::{AuthClaims, TokenPair};
+use crate::rbac::Grants;
+
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+// Tells personal access tokens apart from JWTs in the Authorization header
+pub const PAT_PREFIX: &str = "smp_";
+
+// Picks another of the caller's workspaces for one request, e.g. for
+// personal access tokens
+pub const WORKSPACE_HEADER: &str = "X-Workspace-Id";
+
+// Client address. Forwarded/X-Forwarded-For are only believed from the
+// proxies listed in TRUSTED_PROXIES (comma-separated addresses); from
+// anyone else they could name any address.
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr().map(|addr| addr.ip().to_string());
+    let trusted = std::env::var("TRUSTED_PROXIES").unwrap_or_default();
+    let behind_proxy = peer.as_deref().is_some_and(|peer| trusted.split(',').any(|p| p.trim() == peer));
+    if behind_proxy {
+        if let Some(ip) = req.connection_info().realip_remote_addr() {
+            return Some(ip.to_string());
+        }
+    }
+    peer
+}
+
+// Client address and user agent to show in the session list
+fn client(req: &HttpRequest) -> (Option<String>, Option<String>) {
+    let ip = client_ip(req);
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .map(|ua| ua.chars().take(512).collect());
+    (ip, user_agent)
+}
+
+fn jwt_secret() -> String {
+    std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string())
+}
+
+// Opaque tokens are stored as their SHA-256; they are random enough that
+// a slow hash adds nothing
+pub fn hash_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn access_token(user_id: &str, workspace_id: &str, role: &str, session_id: &str) -> String {
+    let expiration = Utc::now()
+        .checked_add_signed(Duration::minutes(ACCESS_TOKEN_MINUTES))
+        .expect("valid timestamp")
+        .timestamp() as usize;
+
+    let claims = AuthClaims {
+        sub: user_id.to_string(),
+        wid: workspace_id.to_string(),
+        role: role.to_string(),
+        sid: session_id.to_string(),
+        exp: expiration,
+        scope: None,
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_bytes()))
+        .expect("Token generation failed")
+}
+
+// Claims of a correctly signed access token. Logout accepts expired ones,
+// everything else must not.
+pub fn decode_access_token(token: &str, allow_expired: bool) -> Option<AuthClaims> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = !allow_expired;
+    decode::<AuthClaims>(token, &DecodingKey::from_secret(jwt_secret().as_bytes()), &validation)
+        .ok()
+        .map(|data| data.claims)
+}
+
+// The workspace a user lands in when nothing else says: the one they
+// joined first
+pub async fn default_workspace(pool: &DbPool, user_id: &str) -> Result<Option<(String, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT workspace_id, role FROM workspace_members WHERE user_id = $1 ORDER BY created_at, workspace_id LIMIT 1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+// Starts a session in `workspace_id`, e.g. on login
+pub async fn create(pool: &DbPool, req: &HttpRequest, user_id: &str, workspace_id: &str, role: &str) -> Result<TokenPair, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let refresh_token = random_token();
+    let now = Utc::now();
+    let (ip, user_agent) = client(req);
+
+    query(
+        "INSERT INTO sessions (id, user_id, refresh_token_hash, created_at, last_used_at, expires_at, ip, user_agent, workspace_id)
+         VALUES ($1, $2, $3, $4, $4, $5, $6, $7, $8)"
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(hash_token(&refresh_token))
+    .bind(now)
+    .bind(now + Duration::days(REFRESH_TOKEN_DAYS))
+    .bind(ip)
+    .bind(user_agent)
+    .bind(workspace_id)
+    .execute(pool)
+    .await?;
+
+    Ok(TokenPair {
+        token: access_token(user_id, workspace_id, role, &id),
+        refresh_token,
+        expires_in: ACCESS_TOKEN_MINUTES * 60,
+    })
+}
+
+// Swaps a refresh token for a new pair. The old refresh token stops
+// working; if it shows up again it was copied, and the session is revoked.
+pub async fn refresh(pool: &DbPool, req: &HttpRequest, refresh_token: &str) -> Result<Option<TokenPair>, sqlx::Error> {
+    let hash = hash_token(refresh_token);
+    let new_token = random_token();
+    let now = Utc::now();
+    let (ip, user_agent) = client(req);
+
+    // The role comes from the membership, so a changed role takes effect
+    // here. Removed from the session's workspace, the user falls back to
+    // their first one.
+    let rotated: Option<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "UPDATE sessions s SET previous_token_hash = s.refresh_token_hash, refresh_token_hash = $2,
+                last_used_at = $3, expires_at = $4, ip = $5, user_agent = $6,
+                workspace_id = (SELECT m.workspace_id FROM workspace_members m WHERE m.user_id = s.user_id
+                                ORDER BY m.workspace_id IS NOT DISTINCT FROM s.workspace_id DESC, m.created_at, m.workspace_id
+                                LIMIT 1)
+         WHERE s.refresh_token_hash = $1 AND s.revoked_at IS NULL AND s.expires_at > $3
+         RETURNING s.id, s.user_id, s.workspace_id,
+                   (SELECT m.role FROM workspace_members m WHERE m.workspace_id = s.workspace_id AND m.user_id = s.user_id)"
+    )
+    .bind(&hash)
+    .bind(hash_token(&new_token))
+    .bind(now)
+    .bind(now + Duration::days(REFRESH_TOKEN_DAYS))
+    .bind(ip)
+    .bind(user_agent)
+    .fetch_optional(pool)
+    .await?;
+
+    match rotated {
+        Some((id, user_id, workspace_id, role)) => Ok(Some(TokenPair {
+            token: access_token(&user_id, &workspace_id.unwrap_or_default(), &role.unwrap_or_default(), &id),
+            refresh_token: new_token,
+            expires_in: ACCESS_TOKEN_MINUTES * 60,
+        })),
+        None => {
+            query("UPDATE sessions SET revoked_at = $2 WHERE previous_token_hash = $1 AND revoked_at IS NULL")
+                .bind(&hash)
+                .bind(now)
+                .execute(pool)
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+// Makes `workspace_id` the session's workspace. Returns an access token for
+// it, or None when the user isn't a member.
+pub async fn switch(pool: &DbPool, claims: &AuthClaims, workspace_id: &str) -> Result<Option<String>, sqlx::Error> {
+    let role: Option<String> = sqlx::query_scalar(
+        "UPDATE sessions s SET workspace_id = m.workspace_id
+         FROM workspace_members m
+         WHERE s.id = $1 AND s.user_id = $2 AND m.workspace_id = $3 AND m.user_id = s.user_id
+         RETURNING m.role"
+    )
+    .bind(&claims.sid)
+    .bind(&claims.sub)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role.map(|role| access_token(&claims.sub, workspace_id, &role, &claims.sid)))
+}
+
+pub async fn revoke(pool: &DbPool, session_id: &str) -> Result<(), sqlx::Error> {
+    query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(session_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+pub async fn revoke_by_refresh_token(pool: &DbPool, refresh_token: &str) -> Result<(), sqlx::Error> {
+    query("UPDATE sessions SET revoked_at = NOW() WHERE refresh_token_hash = $1 AND revoked_at IS NULL")
+        .bind(hash_token(refresh_token))
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+// Revokes every live session of `user_id` but `except`; returns how many
+pub async fn revoke_all(pool: &DbPool, user_id: &str, except: Option<&str>) -> Result<u64, sqlx::Error> {
+    query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL AND id IS DISTINCT FROM $2")
+        .bind(user_id)
+        .bind(except)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+}
+
+// Live sessions of `user_id`, most recently used first
+pub async fn list(pool: &DbPool, user_id: &str, current: Option<&str>) -> Result<Vec<SessionInfo>, sqlx::Error> {
+    sqlx::query_as::<_, SessionInfo>(
+        "SELECT id, created_at, last_used_at, expires_at, ip, user_agent, id IS NOT DISTINCT FROM $2 AS current
+         FROM sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+         ORDER BY last_used_at DESC"
+    )
+    .bind(user_id)
+    .bind(current)
+    .fetch_all(pool)
+    .await
+}
+
+// Whether the access token's session is live. Deleting a user removes
+// their sessions. Also records the use, at most once a minute per session.
+pub async fn check_access(pool: &DbPool, claims: &AuthClaims) -> bool {
+    let stale: Option<bool> = sqlx::query_scalar(
+        "SELECT last_used_at < NOW() - INTERVAL '1 minute' FROM sessions
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(&claims.sid)
+    .bind(&claims.sub)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let Some(stale) = stale else { return false };
+    if stale {
+        query("UPDATE sessions SET last_used_at = NOW() WHERE id = $1")
+            .bind(&claims.sid)
+            .execute(pool)
+            .await
+            .ok();
+    }
+    true
+}
+
+/// What the caller may do in a workspace
+#[derive(Debug)]
+pub enum Access {
+    // Not a member
+    Denied,
+    Granted(Grants),
+    // The workspace requires 2FA for the user's role and it isn't set up yet
+    TwoFactorSetupRequired(Grants),
+}
+
+#[derive(sqlx::FromRow)]
+struct MemberRow {
+    workspace_id: String,
+    role: String,
+    level: i32,
+    permissions: Vec<String>,
+    needs_2fa: bool,
+}
+
+// The user's role in `workspace_id` (their first workspace when None) with
+// what it allows. Loaded on every request, so role and membership changes
+// apply at once.
+pub async fn authorize(pool: &DbPool, user_id: &str, workspace_id: Option<&str>) -> Access {
+    let row: Option<MemberRow> = sqlx::query_as(
+        "SELECT m.workspace_id, m.role, r.level,
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = r.id) AS permissions,
+                COALESCE(ws.require_2fa_for_admins, FALSE) AND NOT u.totp_enabled AS needs_2fa
+         FROM workspace_members m
+         JOIN users u ON u.id = m.user_id
+         JOIN roles r ON r.name = m.role AND (r.workspace_id IS NULL OR r.workspace_id = m.workspace_id)
+         LEFT JOIN workspace_settings ws ON ws.workspace_id = m.workspace_id
+         WHERE m.user_id = $1 AND ($2::TEXT IS NULL OR m.workspace_id = $2)
+         ORDER BY m.created_at, m.workspace_id
+         LIMIT 1"
+    )
+    .bind(user_id)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let Some(MemberRow { workspace_id, role, level, permissions, needs_2fa }) = row else { return Access::Denied };
+    let grants = Grants { workspace_id, role, level, permissions };
+    if needs_2fa && grants.privileged() {
+        Access::TwoFactorSetupRequired(grants)
+    } else {
+        Access::Granted(grants)
+    }
+}
+
+// Claims for a personal access token, shaped like a session's: `sid` is
+// the token id, and the workspace and role are filled in like a session's.
+// Also records the use, at most once a minute per token.
+pub async fn authenticate_pat(pool: &DbPool, token: &str) -> Option<AuthClaims> {
+    let row: Option<(String, String, String, bool)> = sqlx::query_as(
+        "SELECT id, user_id, scope, last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute'
+         FROM personal_access_tokens
+         WHERE token_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())"
+    )
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let (id, user_id, scope, stale) = row?;
+    if stale {
+        query("UPDATE personal_access_tokens SET last_used_at = NOW() WHERE id = $1")
+            .bind(&id)
+            .execute(pool)
+            .await
+            .ok();
+    }
+
+    Some(AuthClaims {
+        sub: user_id,
+        wid: String::new(),
+        role: String::new(),
+        sid: id,
+        exp: 0, // expiry is checked above, per request
+        scope: Some(scope),
+    })
+}
@@ -0,0 +1,197 @@
+use actix_web::HttpResponse;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::DbPool;
+
+// Failures are forgotten this long after the last one
+const FORGET_AFTER_HOURS: i64 = 24;
+const MAX_DELAY_SECONDS: i64 = 300;
+const MAX_LOCK_HOURS: i64 = 24;
+const REGISTRATIONS_PER_IP_PER_HOUR: i64 = 5;
+
+// After `free` failures each further try has to wait twice as long as the
+// last, and every `lock_every` failures lock for twice as long as before
+struct Policy {
+    scope: &'static str,
+    free: i32,
+    lock_every: i32,
+    lock_minutes: i64,
+    event: &'static str,
+}
+
+const ACCOUNT: Policy = Policy { scope: "account", free: 3, lock_every: 10, lock_minutes: 15, event: "account_locked" };
+// Higher, as many users can share an address
+const IP: Policy = Policy { scope: "ip", free: 10, lock_every: 50, lock_minutes: 15, event: "ip_locked" };
+
+impl Policy {
+    fn delay(&self, failures: i32) -> Duration {
+        if failures <= self.free {
+            return Duration::zero();
+        }
+        let exp = (failures - self.free - 1).min(16) as u32;
+        Duration::seconds(2i64.pow(exp).min(MAX_DELAY_SECONDS))
+    }
+
+    fn lock(&self, failures: i32) -> Option<Duration> {
+        if failures % self.lock_every != 0 {
+            return None;
+        }
+        let exp = (failures / self.lock_every - 1).min(16) as u32;
+        Some(Duration::minutes((self.lock_minutes * 2i64.pow(exp)).min(MAX_LOCK_HOURS * 60)))
+    }
+}
+
+fn too_many(wait: Duration, message: &str) -> HttpResponse {
+    let seconds = wait.num_seconds().max(1);
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", seconds.to_string()))
+        .body(format!("{}, try again in {} seconds", message, seconds))
+}
+
+async fn wait(pool: &DbPool, policy: &Policy, key: &str) -> Result<Option<Duration>, sqlx::Error> {
+    let row: Option<(i32, DateTime<Utc>, Option<DateTime<Utc>>)> = query_as(
+        "SELECT failures, last_failure_at, locked_until FROM login_throttle
+         WHERE scope = $1 AND key = $2 AND (last_failure_at > NOW() - make_interval(hours => $3) OR locked_until > NOW())"
+    )
+    .bind(policy.scope)
+    .bind(key)
+    .bind(FORGET_AFTER_HOURS as i32)
+    .fetch_optional(pool)
+    .await?;
+    let Some((failures, last_failure_at, locked_until)) = row else { return Ok(None) };
+
+    let now = Utc::now();
+    let until = (last_failure_at + policy.delay(failures)).max(locked_until.unwrap_or(now));
+    Ok((until > now).then(|| until - now))
+}
+
+// Refuses a sign-in attempt while the account or address is locked or
+// still backing off. Runs before the password check, so a correct
+// password does not get through either.
+pub async fn check_login(pool: &DbPool, username: &str, ip: Option<&str>) -> Result<(), HttpResponse> {
+    let account = wait(pool, &ACCOUNT, username).await;
+    let address = match ip {
+        Some(ip) => wait(pool, &IP, ip).await,
+        None => Ok(None),
+    };
+
+    match (account, address) {
+        (Ok(Some(w)), _) => Err(too_many(w, "Too many failed sign-in attempts for this account")),
+        (_, Ok(Some(w))) => Err(too_many(w, "Too many failed sign-in attempts from this address")),
+        (Ok(None), Ok(None)) => Ok(()),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("throttle check error: {:?}", e);
+            Err(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+async fn count_failure(pool: &DbPool, policy: &Policy, key: &str) -> Result<bool, sqlx::Error> {
+    let failures: i32 = query_scalar(
+        "INSERT INTO login_throttle (scope, key, failures, last_failure_at) VALUES ($1, $2, 1, NOW())
+         ON CONFLICT (scope, key) DO UPDATE SET
+             failures = CASE WHEN login_throttle.last_failure_at < NOW() - make_interval(hours => $3)
+                                  AND (login_throttle.locked_until IS NULL OR login_throttle.locked_until < NOW())
+                             THEN 1 ELSE login_throttle.failures + 1 END,
+             last_failure_at = NOW()
+         RETURNING failures"
+    )
+    .bind(policy.scope)
+    .bind(key)
+    .bind(FORGET_AFTER_HOURS as i32)
+    .fetch_one(pool)
+    .await?;
+
+    let Some(lock) = policy.lock(failures) else { return Ok(false) };
+    query("UPDATE login_throttle SET locked_until = $3 WHERE scope = $1 AND key = $2")
+        .bind(policy.scope)
+        .bind(key)
+        .bind(Utc::now() + lock)
+        .execute(pool)
+        .await?;
+    Ok(true)
+}
+
+// Counts a wrong password (`login_failed`) or 2FA code (`two_factor_failed`)
+// against the account and the address, locking them when it's time
+pub async fn record_failure(pool: &DbPool, event: &str, username: &str, ip: Option<&str>) {
+    let user_id: Option<String> = query_scalar("SELECT id FROM users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    log(pool, event, Some(username), user_id.as_deref(), ip, None).await;
+
+    match count_failure(pool, &ACCOUNT, username).await {
+        Ok(true) => log(pool, ACCOUNT.event, Some(username), user_id.as_deref(), ip, None).await,
+        Ok(false) => {}
+        Err(e) => eprintln!("throttle record error: {:?}", e),
+    }
+    if let Some(ip) = ip {
+        match count_failure(pool, &IP, ip).await {
+            Ok(true) => log(pool, IP.event, None, None, Some(ip), None).await,
+            Ok(false) => {}
+            Err(e) => eprintln!("throttle record error: {:?}", e),
+        }
+    }
+}
+
+// Starts the account over, after a successful sign-in or an admin unlock.
+// The address keeps its count, or one good account would clear it.
+pub async fn clear_account(pool: &DbPool, username: &str) -> Result<bool, sqlx::Error> {
+    query("DELETE FROM login_throttle WHERE scope = 'account' AND key = $1")
+        .bind(username)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected() > 0)
+}
+
+// Open registration is limited per address
+pub async fn check_register(pool: &DbPool, ip: Option<&str>) -> Result<(), HttpResponse> {
+    let Some(ip) = ip else { return Ok(()) };
+    let recent: Result<Option<DateTime<Utc>>, sqlx::Error> = query_scalar(
+        "SELECT MIN(created_at) FROM (
+             SELECT created_at FROM login_events
+             WHERE event = 'registered' AND ip = $1 AND created_at > NOW() - INTERVAL '1 hour'
+             ORDER BY created_at DESC LIMIT $2
+         ) r
+         HAVING COUNT(*) >= $2"
+    )
+    .bind(ip)
+    .bind(REGISTRATIONS_PER_IP_PER_HOUR)
+    .fetch_optional(pool)
+    .await
+    .map(Option::flatten);
+
+    match recent {
+        Ok(None) => Ok(()),
+        Ok(Some(oldest)) => Err(too_many(oldest + Duration::hours(1) - Utc::now(), "Too many registrations from this address")),
+        Err(e) => {
+            eprintln!("register throttle error: {:?}", e);
+            Err(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+pub async fn log(
+    pool: &DbPool,
+    event: &str,
+    username: Option<&str>,
+    user_id: Option<&str>,
+    ip: Option<&str>,
+    actor_id: Option<&str>,
+) {
+    let result = query("INSERT INTO login_events (event, username, user_id, ip, actor_id) VALUES ($1, $2, $3, $4, $5)")
+        .bind(event)
+        .bind(username)
+        .bind(user_id)
+        .bind(ip)
+        .bind(actor_id)
+        .execute(pool)
+        .await;
+
+    if let Err(e) = result {
+        eprintln!("login event log error: {:?}", e);
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use sqlx::{query, query_as, query_scalar};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::rbac;
+use crate::session;
+
+const ISSUER: &str = "Sic Mundus";
+const STEP_SECONDS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+pub const CHALLENGE_MINUTES: i64 = 5;
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+fn totp(secret: &str, account: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    Some(TOTP::new_unchecked(Algorithm::SHA1, 6, 1, STEP_SECONDS, bytes, Some(ISSUER.to_string()), account.to_string()))
+}
+
+// 160 random bits, base32 as authenticator apps expect
+pub fn new_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    match Secret::Raw(bytes.to_vec()).to_encoded() {
+        Secret::Encoded(s) => s,
+        Secret::Raw(_) => unreachable!(),
+    }
+}
+
+// otpauth:// URI for QR codes
+pub fn provisioning_uri(secret: &str, account: &str) -> Option<String> {
+    totp(secret, account).map(|t| t.get_url())
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Time step `code` is valid for, allowing one step of clock drift either
+// way. Steps up to `last_step` were already used and don't count.
+pub fn verify(secret: &str, code: &str, last_step: i64) -> Option<i64> {
+    verify_at(secret, code, last_step, Utc::now().timestamp())
+}
+
+fn verify_at(secret: &str, code: &str, last_step: i64, now: i64) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let totp = totp(secret, "")?;
+    let current = (now as u64 / STEP_SECONDS) as i64;
+    (current - 1..=current + 1)
+        .filter(|step| *step > last_step)
+        .find(|step| same(&totp.generate(*step as u64 * STEP_SECONDS), &code))
+}
+
+// Recovery codes are compared without case, spaces or dashes
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+// Replaces all recovery codes of `user_id` and returns the new ones
+pub async fn new_recovery_codes(pool: &DbPool, user_id: &str) -> Result<Vec<String>, sqlx::Error> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10).map(|_| ALPHABET[(rng.next_u32() as usize) % ALPHABET.len()] as char).collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    query("DELETE FROM recovery_codes WHERE user_id = $1").bind(user_id).execute(&mut *tx).await?;
+    for code in &codes {
+        query("INSERT INTO recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(session::hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(codes)
+}
+
+// Checks a TOTP code, or else a recovery code (used up on success), for a
+// user with 2FA enabled
+pub async fn check_code(pool: &DbPool, user_id: &str, code: &str) -> Result<bool, sqlx::Error> {
+    let row: Option<(String, i64)> = query_as(
+        "SELECT totp_secret, totp_last_step FROM users WHERE id = $1 AND totp_enabled AND totp_secret IS NOT NULL"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((secret, last_step)) = row else { return Ok(false) };
+
+    if let Some(step) = verify(&secret, code, last_step) {
+        // Guarded so two requests can't both spend the same code
+        let used = query("UPDATE users SET totp_last_step = $1 WHERE id = $2 AND totp_last_step < $1")
+            .bind(step)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        return Ok(used.rows_affected() == 1);
+    }
+
+    let used = query("UPDATE recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL")
+        .bind(user_id)
+        .bind(session::hash_token(&normalize_recovery_code(code)))
+        .execute(pool)
+        .await?;
+    Ok(used.rows_affected() == 1)
+}
+
+// Token for the second login step. A user has one open challenge at a
+// time, so asking for more doesn't multiply the tries.
+pub async fn create_challenge(pool: &DbPool, user_id: &str) -> Result<String, sqlx::Error> {
+    let token = session::random_token();
+    let now = Utc::now();
+
+    query("DELETE FROM login_challenges WHERE expires_at < $1 OR user_id = $2").bind(now).bind(user_id).execute(pool).await?;
+    query(
+        "INSERT INTO login_challenges (id, user_id, token_hash, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(session::hash_token(&token))
+    .bind(now)
+    .bind(now + Duration::minutes(CHALLENGE_MINUTES))
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+pub enum Redeemed {
+    User(String),
+    // The username, so the failure can be counted against the account
+    WrongCode(String),
+    // Unknown, expired or used-up challenge
+    Invalid,
+}
+
+// Whose open challenge the token is, to throttle before a code is tried
+pub async fn challenge_username(pool: &DbPool, token: &str) -> Result<Option<String>, sqlx::Error> {
+    query_scalar(
+        "SELECT u.username FROM login_challenges c JOIN users u ON u.id = c.user_id
+         WHERE c.token_hash = $1 AND c.expires_at > NOW()"
+    )
+    .bind(session::hash_token(token))
+    .fetch_optional(pool)
+    .await
+}
+
+// Each try counts; a challenge is used up after a success or too many
+// failures.
+pub async fn redeem_challenge(pool: &DbPool, token: &str, code: &str) -> Result<Redeemed, sqlx::Error> {
+    let challenge: Option<(String, String, String)> = query_as(
+        "UPDATE login_challenges c SET attempts = c.attempts + 1
+         FROM users u
+         WHERE u.id = c.user_id AND c.token_hash = $1 AND c.expires_at > NOW() AND c.attempts < $2
+         RETURNING c.id, c.user_id, u.username"
+    )
+    .bind(session::hash_token(token))
+    .bind(MAX_CHALLENGE_ATTEMPTS)
+    .fetch_optional(pool)
+    .await?;
+    let Some((id, user_id, username)) = challenge else { return Ok(Redeemed::Invalid) };
+
+    if !check_code(pool, &user_id, code).await? {
+        return Ok(Redeemed::WrongCode(username));
+    }
+    query("DELETE FROM login_challenges WHERE id = $1").bind(&id).execute(pool).await?;
+    Ok(Redeemed::User(user_id))
+}
+
+// Whether the workspace makes `role` use 2FA: the policy covers every role
+// with more than self-service permissions
+pub async fn required_for(pool: &DbPool, workspace_id: &str, role: &str) -> bool {
+    query_scalar(
+        "SELECT ws.require_2fa_for_admins AND EXISTS (
+             SELECT 1 FROM roles r JOIN role_permissions rp ON rp.role_id = r.id
+             WHERE r.name = $1 AND (r.workspace_id IS NULL OR r.workspace_id = $3)
+               AND NOT (rp.permission = ANY($2))
+         )
+         FROM workspace_settings ws WHERE ws.workspace_id = $3"
+    )
+    .bind(role)
+    .bind(&rbac::BASIC[..])
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None)
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+    const NOW: i64 = 1_790_000_000;
+    const STEP: i64 = NOW / STEP_SECONDS as i64;
+
+    fn code_for(step: i64) -> String {
+        totp(SECRET, "").unwrap().generate(step as u64 * STEP_SECONDS)
+    }
+
+    #[test]
+    fn current_code_is_accepted() {
+        assert_eq!(verify_at(SECRET, &code_for(STEP), 0, NOW), Some(STEP));
+    }
+
+    #[test]
+    fn one_step_of_drift_either_way() {
+        assert_eq!(verify_at(SECRET, &code_for(STEP - 1), 0, NOW), Some(STEP - 1));
+        assert_eq!(verify_at(SECRET, &code_for(STEP + 1), 0, NOW), Some(STEP + 1));
+        assert_eq!(verify_at(SECRET, &code_for(STEP - 2), 0, NOW), None);
+        assert_eq!(verify_at(SECRET, &code_for(STEP + 2), 0, NOW), None);
+    }
+
+    #[test]
+    fn used_steps_are_not_replayed() {
+        assert_eq!(verify_at(SECRET, &code_for(STEP), STEP, NOW), None);
+        assert_eq!(verify_at(SECRET, &code_for(STEP - 1), STEP - 1, NOW), None);
+        // A later step is still good after an earlier one was used
+        assert_eq!(verify_at(SECRET, &code_for(STEP + 1), STEP, NOW), Some(STEP + 1));
+    }
+
+    #[test]
+    fn spaces_in_codes_are_ignored() {
+        let code = code_for(STEP);
+        let spaced = format!("{} {}", &code[..3], &code[3..]);
+        assert_eq!(verify_at(SECRET, &spaced, 0, NOW), Some(STEP));
+    }
+
+    #[test]
+    fn wrong_codes_and_secrets_fail() {
+        let wrong = if code_for(STEP) == "000000" { "111111" } else { "000000" };
+        assert_eq!(verify_at(SECRET, wrong, 0, NOW), None);
+        assert_eq!(verify_at(SECRET, &code_for(STEP)[..5], 0, NOW), None);
+        assert_eq!(verify_at("not base32!", "123456", 0, NOW), None);
+    }
+
+    #[test]
+    fn recovery_codes_ignore_case_and_dashes() {
+        assert_eq!(normalize_recovery_code("AbCdE-fGh23"), "abcdefgh23");
+        assert_eq!(normalize_recovery_code(" abcde fgh23 "), "abcdefgh23");
+    }
+
+    #[test]
+    fn comparison_needs_equal_strings() {
+        assert!(same("123456", "123456"));
+        assert!(!same("123456", "123457"));
+        assert!(!same("12345", "123456"));
+    }
+}
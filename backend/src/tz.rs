@@ -0,0 +1,123 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::db::DbPool;
+
+pub fn parse(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone: {}", name))
+}
+
+// Timezone used for a request: an explicit `?tz=` override, otherwise the
+// user's stored zone, otherwise UTC.
+pub async fn resolve(pool: &DbPool, user_id: &str, tz_override: Option<&str>) -> Result<Tz, String> {
+    if let Some(name) = tz_override.filter(|n| !n.is_empty()) {
+        return parse(name);
+    }
+
+    let stored: Option<String> = sqlx::query_scalar("SELECT timezone FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+
+    Ok(stored.and_then(|n| n.parse().ok()).unwrap_or(Tz::UTC))
+}
+
+pub fn today(tz: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&tz).date_naive()
+}
+
+// UTC instant at which `day` starts in `tz`. When local midnight falls in
+// a DST gap the day starts at the first valid local time after it.
+pub fn start_of_day(tz: Tz, day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_time(NaiveTime::MIN);
+    (0..24)
+        .find_map(|h| tz.from_local_datetime(&(midnight + Duration::hours(h))).earliest())
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+pub fn first_of_month(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+// Range bound from a query string: an RFC3339 timestamp is taken as-is, a
+// bare `YYYY-MM-DD` is a whole local day (so `to` covers that day too).
+pub fn parse_bound(tz: Tz, val: &str, is_end: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(val) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(val, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date or timestamp: {}", val))?;
+    Ok(start_of_day(tz, if is_end { day + Duration::days(1) } else { day }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn day_starts_at_local_midnight() {
+        let berlin = parse("Europe/Berlin").unwrap();
+        assert_eq!(start_of_day(berlin, day("2026-01-15")), utc("2026-01-14T23:00:00Z"));
+        assert_eq!(start_of_day(berlin, day("2026-07-15")), utc("2026-07-14T22:00:00Z"));
+        assert_eq!(start_of_day(Tz::UTC, day("2026-07-15")), utc("2026-07-15T00:00:00Z"));
+    }
+
+    #[test]
+    fn dst_change_days_start_at_midnight_before_the_switch() {
+        // Berlin switches at 02:00/03:00, so midnight still exists
+        let berlin = parse("Europe/Berlin").unwrap();
+        assert_eq!(start_of_day(berlin, day("2026-03-29")), utc("2026-03-28T23:00:00Z"));
+        assert_eq!(start_of_day(berlin, day("2026-10-25")), utc("2026-10-24T22:00:00Z"));
+    }
+
+    #[test]
+    fn day_without_midnight_starts_after_the_gap() {
+        // São Paulo skipped from 00:00 to 01:00 on 2018-11-04
+        let sao_paulo = parse("America/Sao_Paulo").unwrap();
+        assert_eq!(start_of_day(sao_paulo, day("2018-11-04")), utc("2018-11-04T03:00:00Z"));
+    }
+
+    #[test]
+    fn repeated_midnight_takes_the_first() {
+        // Havana fell back from 01:00 to 00:00 on 2022-11-06
+        let havana = parse("America/Havana").unwrap();
+        assert_eq!(start_of_day(havana, day("2022-11-06")), utc("2022-11-06T04:00:00Z"));
+    }
+
+    #[test]
+    fn dates_are_whole_local_days() {
+        let berlin = parse("Europe/Berlin").unwrap();
+        assert_eq!(parse_bound(berlin, "2026-03-29", false).unwrap(), utc("2026-03-28T23:00:00Z"));
+        // The day after, which is an hour shorter
+        assert_eq!(parse_bound(berlin, "2026-03-29", true).unwrap(), utc("2026-03-29T22:00:00Z"));
+    }
+
+    #[test]
+    fn timestamps_are_taken_as_is() {
+        let berlin = parse("Europe/Berlin").unwrap();
+        assert_eq!(parse_bound(berlin, "2026-03-29T10:30:00+05:00", true).unwrap(), utc("2026-03-29T05:30:00Z"));
+    }
+
+    #[test]
+    fn bad_bounds_and_zones_are_rejected() {
+        assert!(parse_bound(Tz::UTC, "29.03.2026", false).is_err());
+        assert!(parse_bound(Tz::UTC, "2026-02-30", false).is_err());
+        assert!(parse("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn first_of_month_keeps_the_month() {
+        assert_eq!(first_of_month(day("2026-02-28")), day("2026-02-01"));
+    }
+}